@@ -4,10 +4,16 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use dao_escrow::msg::{
-    ExecuteMsg, InstantiateMsg, QueryMsg, WithdrawalReadyResponse, WithdrawalRequestedResponse,
+    AcceptedTokensResponse, AgreementsResponse, BudgetResponse, Cw20BalanceResponse,
+    Cw20DepositResponse, DelegationsResponse, DepositsResponse, EvacuationsResponse, ExecuteMsg,
+    InstantiateMsg, KeeperPoolResponse, LastHeartbeatResponse, MigrateMsg, NftsResponse,
+    PendingConfigUpdateResponse, PendingWithdrawalResponse, ProposalsResponse, QueryMsg,
+    ReceiveMsg, SchedulesResponse, SecurityStatusResponse, StreamResponse, SupportedAssetsResponse,
+    VaultBalanceResponse, VaultTransfersResponse, VaultsResponse, VerifyBalancesResponse,
+    WithdrawableResponse, WithdrawalReadyResponse, WithdrawalRequestedResponse,
     WithdrawalTimestampResponse,
 };
-use dao_escrow::state::Config;
+use dao_escrow::state::{Agreement, Config, Proposal};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -18,9 +24,36 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
 
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(WithdrawalTimestampResponse), &out_dir);
     export_schema(&schema_for!(WithdrawalReadyResponse), &out_dir);
     export_schema(&schema_for!(WithdrawalRequestedResponse), &out_dir);
+    export_schema(&schema_for!(LastHeartbeatResponse), &out_dir);
+    export_schema(&schema_for!(SecurityStatusResponse), &out_dir);
+    export_schema(&schema_for!(Cw20BalanceResponse), &out_dir);
+    export_schema(&schema_for!(Cw20DepositResponse), &out_dir);
+    export_schema(&schema_for!(AcceptedTokensResponse), &out_dir);
+    export_schema(&schema_for!(PendingConfigUpdateResponse), &out_dir);
+    export_schema(&schema_for!(DepositsResponse), &out_dir);
+    export_schema(&schema_for!(EvacuationsResponse), &out_dir);
+    export_schema(&schema_for!(WithdrawableResponse), &out_dir);
+    export_schema(&schema_for!(VaultBalanceResponse), &out_dir);
+    export_schema(&schema_for!(VaultsResponse), &out_dir);
+    export_schema(&schema_for!(VaultTransfersResponse), &out_dir);
+    export_schema(&schema_for!(BudgetResponse), &out_dir);
+    export_schema(&schema_for!(SchedulesResponse), &out_dir);
+    export_schema(&schema_for!(StreamResponse), &out_dir);
+    export_schema(&schema_for!(Agreement), &out_dir);
+    export_schema(&schema_for!(AgreementsResponse), &out_dir);
+    export_schema(&schema_for!(VerifyBalancesResponse), &out_dir);
+    export_schema(&schema_for!(SupportedAssetsResponse), &out_dir);
+    export_schema(&schema_for!(NftsResponse), &out_dir);
+    export_schema(&schema_for!(DelegationsResponse), &out_dir);
+    export_schema(&schema_for!(Proposal), &out_dir);
+    export_schema(&schema_for!(ProposalsResponse), &out_dir);
+    export_schema(&schema_for!(KeeperPoolResponse), &out_dir);
+    export_schema(&schema_for!(PendingWithdrawalResponse), &out_dir);
 }
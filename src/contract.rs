@@ -1,46 +1,148 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, to_binary, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response,
+    StdResult,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
 use crate::error::ContractError;
 use crate::execute::{
-    execute_cw20_withdraw, execute_escrow_cw20_withdraw, execute_receive, execute_withdraw,
-    override_withdraw, start_withdraw, update_override_address, update_withdrawal_address,
+    approve_milestone, cancel_agreement, cancel_config_update, cancel_proposal,
+    cancel_renounce_override, cancel_schedule, cancel_stream, cancel_vault_transfer, claim_rewards,
+    claim_scheduled_payment, create_agreement, create_schedule, create_stream, delegate,
+    deposit_cw20, dispute_milestone, evacuate, execute_config_update, execute_cw20_withdraw,
+    execute_nft_withdraw, execute_proposal, execute_receive, execute_receive_nft,
+    execute_vault_transfer, execute_withdraw, fund_keeper_pool, heartbeat, lock_field,
+    override_withdraw, override_withdraw_with_signature, pause_schedule, propose_execution,
+    redelegate, refund_deposit, refund_milestone, renounce_override, resolve_dispute,
+    resume_schedule, start_config_update, start_nft_withdraw, start_recovery_withdraw,
+    start_vault_transfer, start_withdraw, sync_cw20_balance, undelegate, update_override_address,
+    update_withdrawal_address, validate_execution_allow_list, withdraw_from_stream,
 };
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::helpers::{
+    is_older_version, query_cw20_balance, validate_delays, validate_guardian_key,
+    validate_native_denom,
+};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::query::{
-    get_withdraw_ready, get_withdraw_requested, query_config, query_withdraw_ready,
+    get_agreement, get_budget, get_cw20_balance, get_cw20_deposit, get_keeper_pool,
+    get_last_heartbeat, get_pending_config_update, get_pending_withdrawal, get_proposal,
+    get_security_status, get_stream, get_vault_balance, get_withdraw_ready, get_withdraw_requested,
+    get_withdrawable, list_accepted_tokens, list_agreements, list_delegations, list_deposits,
+    list_evacuations, list_nfts, list_proposals, list_schedules, list_supported_assets,
+    list_vault_transfers, list_vaults, query_config, query_withdraw_ready, verify_balances,
+};
+use crate::state::{
+    Config, LockableField, ACCEPTED_CW20S, CONFIG, CW20_BALANCES, LAST_OVERRIDE_HEARTBEAT,
+    LAST_WITHDRAW_HEARTBEAT, LEGACY_IMMUTABILITY, NATIVE_DENOMS,
 };
-use crate::state::{Config, CONFIG};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:dao-escrow";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+// the version the CW20 ledger was re-keyed by CW20 contract
+const CW20_LEDGER_VERSION: &str = "0.3.0";
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let withdraw_address = deps.api.addr_validate(&msg.withdraw_address)?;
     let override_address = deps.api.addr_validate(&msg.override_address)?;
 
-    let config = Config {
-        override_address: override_address.clone(),
+    // recovery is all or nothing
+    let recovery_address = match (msg.recovery_address, msg.inactivity_period_in_days) {
+        (Some(address), Some(days)) if days > 0 => Some(deps.api.addr_validate(&address)?),
+        (None, None) => None,
+        _ => return Err(ContractError::InvalidRecoveryConfig {}),
+    };
+
+    // a fallback only makes sense if the override_address has to check in
+    let fallback_override_address = match (
+        msg.fallback_override_address,
+        msg.override_heartbeat_period_in_days,
+    ) {
+        (_, Some(0)) => return Err(ContractError::InvalidOverrideHeartbeatConfig {}),
+        (Some(address), Some(_)) => Some(deps.api.addr_validate(&address)?),
+        (Some(_), None) => return Err(ContractError::InvalidOverrideHeartbeatConfig {}),
+        (None, _) => None,
+    };
+
+    if let Some(allow_list) = &msg.execution_allow_list {
+        validate_execution_allow_list(deps.api, allow_list)?;
+    }
+    if let Some(tip) = &msg.keeper_tip {
+        validate_native_denom(deps.api, &tip.denom)?;
+    }
+    if let Some(key) = &msg.guardian_key {
+        validate_guardian_key(key)?;
+    }
+
+    // the override_address can't evacuate to itself
+    let safe_harbor_address = match msg.safe_harbor_address {
+        Some(address) => {
+            let address = deps.api.addr_validate(&address)?;
+            if address == override_address || Some(&address) == fallback_override_address.as_ref() {
+                return Err(ContractError::InvalidSafeHarbor {});
+            }
+            Some(address)
+        }
+        None => None,
+    };
+
+    validate_delays(msg.withdraw_delay_in_days, msg.reserve_delay_in_days)?;
+
+    let mut locked_fields = vec![];
+    if msg.set_withdraw_as_immutable {
+        locked_fields.push(LockableField::WithdrawAddress);
+    }
+    if msg.set_override_as_immutable {
+        locked_fields.push(LockableField::OverrideAddress);
+    }
+
+    let mut config = Config {
+        override_address: Some(override_address.clone()),
         withdraw_address: withdraw_address.clone(),
-        set_override_as_immutable: msg.set_override_as_immutable,
-        set_withdraw_as_immutable: msg.set_withdraw_as_immutable,
+        locked_fields,
         withdraw_delay_in_days: msg.withdraw_delay_in_days,
-        native_denom: msg.native_denom,
         enable_cw20_receive: msg.enable_cw20_receive,
+        recovery_address,
+        inactivity_period_in_days: msg.inactivity_period_in_days,
+        override_heartbeat_period_in_days: msg.override_heartbeat_period_in_days,
+        fallback_override_address,
+        unbonding_period_in_days: msg.unbonding_period_in_days,
+        execution_allow_list: msg.execution_allow_list,
+        permissionless_execution: msg.permissionless_execution,
+        keeper_tip: msg.keeper_tip,
+        guardian_key: msg.guardian_key,
+        safe_harbor_address,
+        reserve_floors: vec![],
+        reserve_delay_in_days: msg.reserve_delay_in_days,
+        deposit_refund_period_in_days: msg.deposit_refund_period_in_days,
     };
+    for floor in msg.reserve_floors {
+        config.set_reserve_floor(floor.denom_or_address, floor.amount);
+    }
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
     CONFIG.save(deps.storage, &config)?;
 
+    for address in msg.accepted_cw20_addresses {
+        let cw20_addr = deps.api.addr_validate(&address)?;
+        ACCEPTED_CW20S.save(deps.storage, cw20_addr, &Empty {})?;
+    }
+
+    for denom in msg.native_denoms {
+        validate_native_denom(deps.api, &denom)?;
+        NATIVE_DENOMS.save(deps.storage, denom, &Empty {})?;
+    }
+
+    // the inactivity timers start now
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+    LAST_OVERRIDE_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
         .add_attribute("override_address", override_address)
@@ -59,22 +161,147 @@ pub fn execute(
         ExecuteMsg::StartWithdraw {
             denom_or_address,
             amount,
-        } => start_withdraw(deps, env, info, denom_or_address, amount),
+            auto_undelegate,
+            use_reserve_delay,
+            vault,
+            category,
+        } => start_withdraw(
+            deps,
+            env,
+            info,
+            denom_or_address,
+            amount,
+            auto_undelegate,
+            use_reserve_delay,
+            vault,
+            category,
+        ),
         ExecuteMsg::ExecuteNativeWithdraw { denom } => execute_withdraw(deps, env, info, denom),
-        ExecuteMsg::ExecuteCW20Withdraw { address } => {
+        ExecuteMsg::ExecuteCW20Withdraw { address }
+        | ExecuteMsg::ExecuteEscrowCW20Withdraw { address } => {
             execute_cw20_withdraw(deps, env, info, address)
         }
-        ExecuteMsg::ExecuteEscrowCW20Withdraw { address } => {
-            execute_escrow_cw20_withdraw(deps, env, info, address)
-        }
         ExecuteMsg::OverrideWithdraw {} => override_withdraw(deps, env, info),
+        ExecuteMsg::OverrideWithdrawWithSignature {
+            withdrawal_id,
+            nonce,
+            signature,
+        } => override_withdraw_with_signature(deps, env, withdrawal_id, nonce, signature),
         ExecuteMsg::UpdateOverrideAddress { address } => {
             update_override_address(deps, env, info, address)
         }
         ExecuteMsg::UpdateWithdrawalAddress { address } => {
             update_withdrawal_address(deps, env, info, address)
         }
+        ExecuteMsg::RenounceOverride {} => renounce_override(deps, env, info),
+        ExecuteMsg::CancelRenounceOverride {} => cancel_renounce_override(deps, env, info),
+        ExecuteMsg::LockField { field } => lock_field(deps, env, info, field),
+        ExecuteMsg::Heartbeat {} => heartbeat(deps, env, info),
+        ExecuteMsg::StartRecoveryWithdraw {
+            denom_or_address,
+            amount,
+        } => start_recovery_withdraw(deps, env, info, denom_or_address, amount),
+        ExecuteMsg::StartConfigUpdate { update } => start_config_update(deps, env, info, update),
+        ExecuteMsg::ExecuteConfigUpdate {} => execute_config_update(deps, env, info),
+        ExecuteMsg::CancelConfigUpdate {} => cancel_config_update(deps, env, info),
+        ExecuteMsg::SyncCw20Balance { address } => sync_cw20_balance(deps, env, info, address),
+        ExecuteMsg::DepositCw20 { token, amount } => deposit_cw20(deps, env, info, token, amount),
         ExecuteMsg::Receive(wrapped) => execute_receive(deps, env, info, wrapped),
+        ExecuteMsg::StartNftWithdraw {
+            collection,
+            token_ids,
+        } => start_nft_withdraw(deps, env, info, collection, token_ids),
+        ExecuteMsg::ExecuteNftWithdraw { collection } => {
+            execute_nft_withdraw(deps, env, info, collection)
+        }
+        ExecuteMsg::ReceiveNft(wrapped) => execute_receive_nft(deps, info, wrapped),
+        ExecuteMsg::Delegate { validator, amount } => delegate(deps, env, info, validator, amount),
+        ExecuteMsg::Undelegate { validator, amount } => undelegate(deps, info, validator, amount),
+        ExecuteMsg::Redelegate {
+            src_validator,
+            dst_validator,
+            amount,
+        } => redelegate(deps, info, src_validator, dst_validator, amount),
+        ExecuteMsg::ClaimRewards {} => claim_rewards(deps, env, info),
+        ExecuteMsg::ProposeExecution { msgs } => propose_execution(deps, env, info, msgs),
+        ExecuteMsg::ExecuteProposal { id } => execute_proposal(deps, env, info, id),
+        ExecuteMsg::CancelProposal { id } => cancel_proposal(deps, env, info, id),
+        ExecuteMsg::FundKeeperPool {} => fund_keeper_pool(deps, info),
+        ExecuteMsg::Evacuate { assets } => evacuate(deps, env, info, assets),
+        ExecuteMsg::StartVaultTransfer {
+            from,
+            to,
+            denom_or_address,
+            amount,
+        } => start_vault_transfer(deps, env, info, from, to, denom_or_address, amount),
+        ExecuteMsg::ExecuteVaultTransfer { id } => execute_vault_transfer(deps, env, info, id),
+        ExecuteMsg::CancelVaultTransfer { id } => cancel_vault_transfer(deps, env, info, id),
+        ExecuteMsg::CreateSchedule {
+            recipient,
+            denom_or_address,
+            amount,
+            interval_in_days,
+            count,
+        } => create_schedule(
+            deps,
+            env,
+            info,
+            recipient,
+            denom_or_address,
+            amount,
+            interval_in_days,
+            count,
+        ),
+        ExecuteMsg::ClaimScheduledPayment { id } => claim_scheduled_payment(deps, env, id),
+        ExecuteMsg::PauseSchedule { id } => pause_schedule(deps, env, info, id),
+        ExecuteMsg::ResumeSchedule { id } => resume_schedule(deps, env, info, id),
+        ExecuteMsg::CancelSchedule { id } => cancel_schedule(deps, env, info, id),
+        ExecuteMsg::CreateStream {
+            recipient,
+            denom_or_address,
+            total,
+            start,
+            end,
+        } => create_stream(
+            deps,
+            env,
+            info,
+            recipient,
+            denom_or_address,
+            total,
+            start,
+            end,
+        ),
+        ExecuteMsg::WithdrawFromStream { id } => withdraw_from_stream(deps, env, info, id),
+        ExecuteMsg::CancelStream { id } => cancel_stream(deps, env, info, id),
+        ExecuteMsg::CreateAgreement {
+            payee,
+            arbiter,
+            denom_or_address,
+            milestones,
+        } => create_agreement(
+            deps,
+            env,
+            info,
+            payee,
+            arbiter,
+            denom_or_address,
+            milestones,
+        ),
+        ExecuteMsg::CancelAgreement { id } => cancel_agreement(deps, env, info, id),
+        ExecuteMsg::ApproveMilestone { id, milestone } => {
+            approve_milestone(deps, env, info, id, milestone)
+        }
+        ExecuteMsg::DisputeMilestone { id, milestone } => {
+            dispute_milestone(deps, env, info, id, milestone)
+        }
+        ExecuteMsg::ResolveDispute {
+            id,
+            milestone,
+            release,
+        } => resolve_dispute(deps, env, info, id, milestone, release),
+        ExecuteMsg::RefundMilestone { id, milestone } => refund_milestone(deps, env, id, milestone),
+        ExecuteMsg::RefundDeposit { id } => refund_deposit(deps, env, info, id),
     }
 }
 
@@ -85,5 +312,122 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::GetWithdrawalReadyTime {} => to_binary(&get_withdraw_ready(deps)?),
         QueryMsg::IsWithdrawalReady {} => to_binary(&query_withdraw_ready(deps, env)?),
         QueryMsg::GetWithdrawalRequested {} => to_binary(&get_withdraw_requested(deps, env)?),
+        QueryMsg::GetLastHeartbeat {} => to_binary(&get_last_heartbeat(deps)?),
+        QueryMsg::GetSecurityStatus {} => to_binary(&get_security_status(deps, env)?),
+        QueryMsg::GetCw20Balance { address } => to_binary(&get_cw20_balance(deps, address)?),
+        QueryMsg::GetCw20Deposit { address, depositor } => {
+            to_binary(&get_cw20_deposit(deps, address, depositor)?)
+        }
+        QueryMsg::ListAcceptedTokens { start_after, limit } => {
+            to_binary(&list_accepted_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::GetPendingWithdrawal {} => to_binary(&get_pending_withdrawal(deps)?),
+        QueryMsg::GetPendingConfigUpdate {} => to_binary(&get_pending_config_update(deps)?),
+        QueryMsg::ListDeposits { start_after, limit } => {
+            to_binary(&list_deposits(deps, start_after, limit)?)
+        }
+        QueryMsg::VerifyBalances {} => to_binary(&verify_balances(deps, env)?),
+        QueryMsg::ListSupportedAssets {} => to_binary(&list_supported_assets(deps, env)?),
+        QueryMsg::GetVaultBalance {
+            vault,
+            denom_or_address,
+        } => to_binary(&get_vault_balance(deps, env, vault, denom_or_address)?),
+        QueryMsg::GetProposal { id } => to_binary(&get_proposal(deps, id)?),
+        QueryMsg::ListProposals { start_after, limit } => {
+            to_binary(&list_proposals(deps, start_after, limit)?)
+        }
+        QueryMsg::GetKeeperPool {} => to_binary(&get_keeper_pool(deps)?),
+        QueryMsg::ListDelegations {} => to_binary(&list_delegations(deps, env)?),
+        QueryMsg::ListNfts {
+            collection,
+            start_after,
+            limit,
+        } => to_binary(&list_nfts(deps, collection, start_after, limit)?),
+        QueryMsg::GetWithdrawable {
+            denom_or_address,
+            vault,
+        } => to_binary(&get_withdrawable(deps, env, denom_or_address, vault)?),
+        QueryMsg::ListVaults { start_after, limit } => {
+            to_binary(&list_vaults(deps, start_after, limit)?)
+        }
+        QueryMsg::ListVaultTransfers { start_after, limit } => {
+            to_binary(&list_vault_transfers(deps, start_after, limit)?)
+        }
+        QueryMsg::GetBudget { category } => to_binary(&get_budget(deps, env, category)?),
+        QueryMsg::GetStream { id } => to_binary(&get_stream(deps, env, id)?),
+        QueryMsg::GetAgreement { id } => to_binary(&get_agreement(deps, id)?),
+        QueryMsg::ListAgreements { start_after, limit } => {
+            to_binary(&list_agreements(deps, start_after, limit)?)
+        }
+        QueryMsg::ListSchedules { start_after, limit } => {
+            to_binary(&list_schedules(deps, start_after, limit)?)
+        }
+        QueryMsg::ListEvacuations { start_after, limit } => {
+            to_binary(&list_evacuations(deps, start_after, limit)?)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    // only migrate from this contract
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::CustomError {
+            val: format!("Cannot migrate from {}", version.contract),
+        });
+    }
+
+    // older versions keyed the ledger by depositor, not by CW20 contract
+    // those entries can't be trusted, so throw the lot away
+    // anything newer is keyed correctly, and is left alone
+    let mut stale_keys = vec![];
+    if is_older_version(&version.version, CW20_LEDGER_VERSION) {
+        stale_keys = CW20_BALANCES
+            .keys(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for key in stale_keys.iter() {
+            CW20_BALANCES.remove(deps.storage, key.clone());
+        }
+
+        // then rebuild it from what we actually hold
+        // and accept those CW20s, or they couldn't be withdrawn or topped up
+        for address in msg.cw20_addresses {
+            let cw20_addr = deps.api.addr_validate(&address)?;
+            let balance = query_cw20_balance(&deps.querier, &cw20_addr, &env.contract.address)?;
+            CW20_BALANCES.save(deps.storage, cw20_addr.clone(), &balance)?;
+            ACCEPTED_CW20S.save(deps.storage, cw20_addr, &Empty {})?;
+        }
+    }
+
+    // older versions only stored a single native_denom, which was never used
+    for denom in msg.native_denoms {
+        validate_native_denom(deps.api, &denom)?;
+        NATIVE_DENOMS.save(deps.storage, denom, &Empty {})?;
+    }
+
+    // older versions stored immutability as bools, which are now locks
+    let legacy = LEGACY_IMMUTABILITY.load(deps.storage)?;
+    let mut config = CONFIG.load(deps.storage)?;
+    if legacy.set_withdraw_as_immutable && !config.is_locked(&LockableField::WithdrawAddress) {
+        config.locked_fields.push(LockableField::WithdrawAddress);
     }
+    if legacy.set_override_as_immutable && !config.is_locked(&LockableField::OverrideAddress) {
+        config.locked_fields.push(LockableField::OverrideAddress);
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    // older versions won't have started the inactivity timers
+    if LAST_WITHDRAW_HEARTBEAT.may_load(deps.storage)?.is_none() {
+        LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+    }
+    if LAST_OVERRIDE_HEARTBEAT.may_load(deps.storage)?.is_none() {
+        LAST_OVERRIDE_HEARTBEAT.save(deps.storage, &env.block.time)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("stale_cw20_balances_removed", stale_keys.len().to_string()))
 }
@@ -1,6 +1,8 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp, Uint128};
 use thiserror::Error;
 
+use crate::state::LockableField;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -12,6 +14,18 @@ pub enum ContractError {
     #[error("Error - CW20 Receive not enabled")]
     CW20ReceiveDisabled,
 
+    #[error("Error - this CW20 is not on the list of accepted tokens")]
+    CW20TokenNotAccepted {},
+
+    #[error("Error - the CW20 receive message was not recognised")]
+    InvalidReceiveMsg {},
+
+    #[error("Error - memo can be at most {max} characters")]
+    MemoTooLong { max: usize },
+
+    #[error("Error - vault name must be between 1 and {max} characters")]
+    InvalidVaultName { max: usize },
+
     #[error("Error while calculating CW20 balance")]
     CW20BalanceError,
 
@@ -27,6 +41,15 @@ pub enum ContractError {
     #[error("Withdrawal CW20 contract address does not match the requested one")]
     WithdrawalCW20Mismatch {},
 
+    #[error("Withdrawal is not for NFTs from this collection")]
+    WithdrawalNftMismatch {},
+
+    #[error("Error - an NFT withdrawal needs at least one token id")]
+    NoNftsRequested {},
+
+    #[error("Error - NFT {token_id} is not held in escrow")]
+    NftNotEscrowed { token_id: String },
+
     #[error("Withdrawal address does not match the requested one")]
     WithdrawalAddressMismatch {},
 
@@ -39,12 +62,194 @@ pub enum ContractError {
     #[error("A native balance was not found in the Contract balances")]
     NoNativeBalance {},
 
+    #[error("Config update not yet requested")]
+    ConfigUpdateNotRequested {},
+
+    #[error("A config update is already pending - cancel it before starting another")]
+    ConfigUpdatePending {},
+
+    #[error("Config update not ready - wait until after timeout has passed")]
+    ConfigUpdateNotReady {},
+
+    #[error("Error - {denom} is not a valid native denom")]
+    InvalidDenom { denom: String },
+
+    #[error("Error - {denom} is neither a supported native denom nor a CW20 contract")]
+    UnsupportedDenom { denom: String },
+
+    #[error("No guardian key has been configured")]
+    GuardianKeyNotConfigured {},
+
+    #[error("Error - the guardian key is not a valid public key for its algorithm")]
+    InvalidGuardianKey {},
+
+    #[error("Error - the signature does not match the guardian key")]
+    InvalidSignature {},
+
+    #[error("Error - the nonce must be higher than the last one used")]
+    NonceAlreadyUsed {},
+
+    #[error("Withdrawal id does not match the pending withdrawal")]
+    WithdrawalIdMismatch {},
+
+    #[error("Error - no funds were sent")]
+    NoFundsSent {},
+
+    #[error("Error - a proposal needs at least one message")]
+    EmptyProposal {},
+
+    #[error("Error - this message is not on the execution allow-list")]
+    ExecutionNotAllowed {},
+
+    #[error("Proposal is no longer pending")]
+    ProposalNotPending {},
+
+    #[error("Proposal not ready - wait until after timeout has passed")]
+    ProposalNotReady {},
+
+    #[error("This proposal now needs a longer delay than it was made with - propose it again")]
+    ProposalDelayTooShort {},
+
+    #[error("Error - {validator} is not an active validator")]
+    ValidatorNotFound { validator: String },
+
     #[error("Custom Error val: {val:?}")]
     CustomError { val: String },
 
-    #[error("The Withdrawal address was set as immutable on contract instantiation")]
-    WithdrawalAddressIsImmutable {},
+    #[error("The {field} has been locked, and can't be changed")]
+    FieldLocked { field: LockableField },
+
+    #[error("Recovery address and inactivity period must be set together, with a non-zero period")]
+    InvalidRecoveryConfig {},
+
+    #[error("A fallback override address requires a non-zero override heartbeat period")]
+    InvalidOverrideHeartbeatConfig {},
+
+    #[error("This would take the balance below its reserve floor of {floor} - the reserve delay is needed")]
+    BelowReserveFloor { floor: Uint128 },
+
+    #[error("No reserve delay has been configured")]
+    ReserveDelayNotConfigured {},
+
+    #[error("The reserve delay has to be longer than the withdraw delay")]
+    InvalidReserveDelay {},
+
+    #[error("Error - a delay can be at most 3650 days")]
+    InvalidDelay {},
+
+    #[error("Error - a withdrawal from this vault is limited to {limit}")]
+    VaultLimitExceeded { limit: Uint128 },
+
+    #[error("Not enough in the vault")]
+    InsufficientVaultBalance {},
+
+    #[error("Error - no vault named {name}")]
+    VaultNotFound { name: String },
+
+    #[error("The vault still holds funds")]
+    VaultNotEmpty {},
+
+    #[error("Can't transfer a vault to itself")]
+    SameVault {},
+
+    #[error("Vault transfer not ready - wait until after timeout has passed")]
+    VaultTransferNotReady {},
+
+    #[error("Error - no budget category named {name}")]
+    BudgetCategoryNotFound { name: String },
+
+    #[error("Error - only {remaining} is left in this category's budget for the period")]
+    BudgetExceeded { remaining: Uint128 },
+
+    #[error("Error - budget category name must be between 1 and {max} characters")]
+    InvalidCategoryName { max: usize },
+
+    #[error("Error - a budget period has to be between a day and 3650 days")]
+    InvalidBudgetPeriod {},
+
+    #[error("Error - a schedule needs an amount, an interval of at most 3650 days, and at least one instalment")]
+    InvalidSchedule {},
+
+    #[error("No instalment is due yet")]
+    ScheduleNotDue {},
+
+    #[error("The schedule is paused")]
+    SchedulePaused {},
+
+    #[error("The schedule isn't paused")]
+    ScheduleNotPaused {},
+
+    #[error("Error - a stream needs a total, and has to end after it starts")]
+    InvalidStream {},
+
+    #[error("Error - a stream can't start until after the withdraw delay, at {earliest}")]
+    StreamStartsTooSoon { earliest: Timestamp },
+
+    #[error("Nothing has accrued to withdraw")]
+    NothingToWithdraw {},
+
+    #[error("The stream has already been cancelled")]
+    StreamCancelled {},
+
+    #[error("Error - an agreement needs a payee, and at least one milestone with an amount and a deadline after it's active")]
+    InvalidAgreement {},
+
+    #[error("Agreement not active - wait until after timeout has passed")]
+    AgreementNotActive {},
+
+    #[error("The agreement is already active, so it can only be settled milestone by milestone")]
+    AgreementActive {},
+
+    #[error("Error - no milestone {milestone} in this agreement")]
+    MilestoneNotFound { milestone: u64 },
+
+    #[error("The milestone has already been settled")]
+    MilestoneSettled {},
+
+    #[error("The milestone isn't disputed")]
+    MilestoneNotDisputed {},
+
+    #[error("The milestone's deadline has passed")]
+    DeadlinePassed {},
+
+    #[error("The milestone's deadline hasn't passed yet")]
+    DeadlineNotPassed {},
+
+    #[error("The deposit has already been refunded")]
+    DepositAlreadyRefunded {},
+
+    #[error("The deposit refund period has passed - ask the override address")]
+    RefundPeriodPassed {},
+
+    #[error("The withdraw address and recovery address can't refund their own deposits - withdraw them instead")]
+    SelfRefundNotAllowed {},
+
+    #[error("Error - refunding this deposit would touch funds earmarked by a pending withdrawal")]
+    DepositEarmarked {},
+
+    #[error("No safe harbor address has been configured")]
+    SafeHarborNotConfigured {},
+
+    #[error("The safe harbor address can't hold the override powers")]
+    InvalidSafeHarbor {},
+
+    #[error("No assets to evacuate")]
+    NoAssetsToEvacuate {},
+
+    #[error("The override role has been renounced")]
+    OverrideRenounced {},
+
+    #[error("Renounce not requested")]
+    RenounceNotRequested {},
+
+    #[error("Renounce not ready - wait until after timeout has passed")]
+    RenounceNotReady {},
+
+    #[error("No recovery address has been configured")]
+    RecoveryNotConfigured {},
 
-    #[error("The Override address was set as immutable on contract instantiation")]
-    OverrideAddressIsImmutable {},
+    #[error(
+        "The Withdrawal address is still active - wait until the inactivity period has passed"
+    )]
+    WithdrawAddressStillActive {},
 }
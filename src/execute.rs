@@ -1,18 +1,59 @@
+use std::cmp::Reverse;
+
 use cosmwasm_std::{
-    ensure_eq, to_binary, BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, Timestamp,
-    Uint128, WasmMsg,
+    ensure_eq, from_binary, to_binary, Addr, Api, Attribute, BankMsg, Binary, Coin, CosmosMsg,
+    Deps, DepsMut, DistributionMsg, Empty, Env, MessageInfo, Order, QuerierWrapper, Response,
+    StakingMsg, StdError, StdResult, Storage, Timestamp, Uint128, WasmMsg,
 };
 
 use crate::error::ContractError;
-use crate::state::{Config, Withdrawal, CONFIG, CW20_BALANCES, WITHDRAWAL_READY};
+use crate::helpers::{
+    allocated_to_vaults, budget_spent, check_cw20_balance, committed_to_agreements,
+    committed_to_streams, days_after, guardian_cancellation_digest, pending_out_of_vault,
+    query_escrowed_balance, query_uncommitted_balance, query_vault_balance, validate_delay,
+    validate_delays, validate_guardian_key, validate_native_denom,
+};
+use crate::msg::{MilestoneTerms, ReceiveMsg};
+use crate::state::{
+    Agreement, Asset, BudgetCategory, BudgetSpend, Config, ConfigUpdate, Deposit, Evacuation,
+    ExecutionAllowList, ExecutionMsgType, LockableField, Milestone, MilestoneStatus,
+    PendingConfigUpdate, Proposal, ProposalStatus, Schedule, SignatureAlgorithm, Stream, Vault,
+    VaultTransfer, Withdrawal, ACCEPTED_CW20S, AGREEMENTS, AGREEMENT_COUNT, BUDGET_CATEGORIES,
+    BUDGET_SPEND, COMMITTED_TO_AGREEMENTS, COMMITTED_TO_STREAMS, CONFIG, CW20_BALANCES,
+    CW20_DEPOSITS, DEPOSITS, DEPOSIT_COUNT, EVACUATIONS, EVACUATION_COUNT, KEEPER_POOL,
+    LAST_GUARDIAN_NONCE, LAST_OVERRIDE_HEARTBEAT, LAST_WITHDRAW_HEARTBEAT, NATIVE_DENOMS, NFTS,
+    PENDING_CONFIG_UPDATE, PENDING_RENOUNCE, PROPOSALS, PROPOSAL_COUNT, SCHEDULES, SCHEDULE_COUNT,
+    STREAMS, STREAM_COUNT, VAULTS, VAULT_BALANCES, VAULT_TRANSFERS, VAULT_TRANSFER_COUNT,
+    WITHDRAWAL_COUNT, WITHDRAWAL_READY,
+};
+
+use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, TokenInfoResponse};
+use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+use cw_storage_plus::Map;
+
+// the longest memo we'll store against a deposit
+const MAX_MEMO_LENGTH: usize = 256;
+
+// the longest gap between a schedule's instalments, about ten years
+const MAX_SCHEDULE_INTERVAL_IN_DAYS: u64 = 3650;
+
+// the longest budget period, also about ten years
+const MAX_BUDGET_PERIOD_IN_DAYS: u64 = 3650;
+
+// the longest vault name we'll accept
+const MAX_VAULT_NAME_LENGTH: usize = 32;
 
-use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+// how the default vault shows up in attributes
+const DEFAULT_VAULT: &str = "default";
+
+// the longest budget category name we'll accept
+const MAX_CATEGORY_NAME_LENGTH: usize = 32;
 
 // receive CW20 tokens
 pub fn execute_receive(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     wrapped: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
     // check that config is set to true
@@ -20,97 +61,877 @@ pub fn execute_receive(
     let receive_enabled = config.enable_cw20_receive;
     ensure_eq!(receive_enabled, true, ContractError::CW20ReceiveDisabled {});
 
-    // wrapped.sender is the CW20 contract sending tokens
-    let cw20_addr = deps.api.addr_validate(&wrapped.sender)?;
-    // wrapped.amount is the balance sent
+    // info.sender is the CW20 contract sending tokens
+    let cw20_addr = info.sender;
+    // wrapped.sender is the address that sent them
+    let depositor = deps.api.addr_validate(&wrapped.sender)?;
+
+    // an empty msg is a plain deposit, anything else has to parse
+    let receive_msg = if wrapped.msg.is_empty() {
+        ReceiveMsg::Deposit {}
+    } else {
+        from_binary(&wrapped.msg).map_err(|_| ContractError::InvalidReceiveMsg {})?
+    };
+
+    let (memo, vault) = match receive_msg {
+        ReceiveMsg::Deposit {} => (None, None),
+        ReceiveMsg::DepositWithMemo { memo } => (Some(memo), None),
+        ReceiveMsg::DepositToVault { vault } => (None, Some(vault)),
+    };
+
+    let deposit = credit_cw20_deposit(
+        deps.storage,
+        &env,
+        cw20_addr,
+        depositor,
+        wrapped.amount,
+        memo,
+        vault,
+    )?;
+
+    let balance = CW20_BALANCES.load(deps.storage, deposit.cw20_address.clone())?;
+
+    let mut response = Response::new()
+        .add_attribute("method", "receive")
+        .add_attribute("deposit_id", deposit.id.to_string())
+        .add_attribute("cw20_address", deposit.cw20_address)
+        .add_attribute("depositor", deposit.depositor)
+        .add_attribute("amount", deposit.amount)
+        .add_attribute("balance", balance);
+    if let Some(memo) = deposit.memo {
+        response = response.add_attribute("memo", memo);
+    }
+    if let Some(vault) = deposit.vault {
+        response = response.add_attribute("vault", vault);
+    }
+
+    Ok(response)
+}
+
+// pulls CW20 tokens in via an allowance the sender has granted us
+// for tooling that can't call Send with a hook
+pub fn deposit_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // check that config is set to true
+    let config = CONFIG.load(deps.storage)?;
+    let receive_enabled = config.enable_cw20_receive;
+    ensure_eq!(receive_enabled, true, ContractError::CW20ReceiveDisabled {});
+
+    let cw20_addr = deps.api.addr_validate(&token)?;
+
+    // credit it now, if the TransferFrom fails the whole thing is rolled back
+    let deposit = credit_cw20_deposit(
+        deps.storage,
+        &env,
+        cw20_addr,
+        info.sender,
+        amount,
+        None,
+        None,
+    )?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: deposit.cw20_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+            owner: deposit.depositor.to_string(),
+            recipient: env.contract.address.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "deposit_cw20")
+        .add_attribute("deposit_id", deposit.id.to_string())
+        .add_attribute("cw20_address", deposit.cw20_address)
+        .add_attribute("depositor", deposit.depositor)
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
+// credits a CW20 deposit to the ledger, the depositor, and any vault
+// and records it in the deposit log
+fn credit_cw20_deposit(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cw20_addr: Addr,
+    depositor: Addr,
+    amount: Uint128,
+    memo: Option<String>,
+    vault: Option<String>,
+) -> Result<Deposit, ContractError> {
+    // it had better be a CW20 we've agreed to hold
+    if !ACCEPTED_CW20S.has(storage, cw20_addr.clone()) {
+        return Err(ContractError::CW20TokenNotAccepted {});
+    }
+
+    if let Some(memo) = &memo {
+        if memo.chars().count() > MAX_MEMO_LENGTH {
+            return Err(ContractError::MemoTooLong {
+                max: MAX_MEMO_LENGTH,
+            });
+        }
+    }
+
     // we add to any balance that already exists and save
-    let existing_balance = CW20_BALANCES.may_load(deps.storage, cw20_addr.clone())?;
+    CW20_BALANCES.update(
+        storage,
+        cw20_addr.clone(),
+        |balance| -> Result<_, ContractError> {
+            balance
+                .unwrap_or_default()
+                .checked_add(amount)
+                .map_err(|_| ContractError::CW20BalanceError {})
+        },
+    )?;
+
+    // and keep track of who sent what
+    CW20_DEPOSITS.update(
+        storage,
+        (cw20_addr.clone(), depositor.clone()),
+        |deposited| -> Result<_, ContractError> {
+            deposited
+                .unwrap_or_default()
+                .checked_add(amount)
+                .map_err(|_| ContractError::CW20BalanceError {})
+        },
+    )?;
+
+    // a vault is a named slice of the ledger balance
+    // and only the override can set one up
+    if let Some(vault) = &vault {
+        ensure_vault_exists(storage, vault)?;
+        VAULT_BALANCES.update(
+            storage,
+            (cw20_addr.to_string(), vault.clone()),
+            |balance| -> Result<_, ContractError> {
+                balance
+                    .unwrap_or_default()
+                    .checked_add(amount)
+                    .map_err(|_| ContractError::CW20BalanceError {})
+            },
+        )?;
+    }
+
+    let id = DEPOSIT_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    DEPOSIT_COUNT.save(storage, &id)?;
+
+    let deposit = Deposit {
+        id,
+        cw20_address: cw20_addr,
+        depositor,
+        amount,
+        memo,
+        vault,
+        received_at: env.block.time,
+        refunded: false,
+    };
+    DEPOSITS.save(storage, id, &deposit)?;
+
+    Ok(deposit)
+}
+
+fn validate_vault_name(vault: &str) -> Result<(), ContractError> {
+    let length = vault.chars().count();
+    if length == 0 || length > MAX_VAULT_NAME_LENGTH {
+        return Err(ContractError::InvalidVaultName {
+            max: MAX_VAULT_NAME_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+fn ensure_vault_exists(storage: &dyn Storage, name: &str) -> Result<(), ContractError> {
+    if !VAULTS.has(storage, name.to_string()) {
+        return Err(ContractError::VaultNotFound {
+            name: name.to_string(),
+        });
+    }
+    Ok(())
+}
+
+fn validate_category_name(category: &str) -> Result<(), ContractError> {
+    let length = category.chars().count();
+    if length == 0 || length > MAX_CATEGORY_NAME_LENGTH {
+        return Err(ContractError::InvalidCategoryName {
+            max: MAX_CATEGORY_NAME_LENGTH,
+        });
+    }
+    Ok(())
+}
+
+// saves a withdrawal that will be ready once the configured delay has passed
+fn save_withdrawal(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    denom_or_address: String,
+    amount: Uint128,
+    is_recovery: bool,
+    nft_token_ids: Option<Vec<String>>,
+) -> Result<Timestamp, ContractError> {
+    save_withdrawal_with_delay(
+        deps,
+        env,
+        config,
+        config.withdraw_delay_in_days,
+        Withdrawal {
+            id: 0,
+            ready_at: env.block.time,
+            denom_or_address,
+            amount,
+            is_recovery,
+            nft_token_ids,
+            vault: None,
+            category: None,
+            budget_period: 0,
+            use_reserve_delay: false,
+        },
+    )
+}
+
+// saves a withdrawal that will be ready once the given delay has passed
+// the id and ready_at are filled in here
+// anything not on the reserve delay has to leave the reserve alone
+fn save_withdrawal_with_delay(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    delay_in_days: u64,
+    withdrawal: Withdrawal,
+) -> Result<Timestamp, ContractError> {
+    if withdrawal.nft_token_ids.is_none() && !withdrawal.use_reserve_delay {
+        ensure_above_reserve_floor(
+            deps.as_ref(),
+            env,
+            config,
+            &withdrawal.denom_or_address,
+            withdrawal.amount,
+        )?;
+    }
+
+    // calculate now + configured days
+    let rewards_ready_at: Timestamp = days_after(env.block.time, delay_in_days)?;
+
+    let id = WITHDRAWAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    WITHDRAWAL_COUNT.save(deps.storage, &id)?;
+
+    let withdrawal = Withdrawal {
+        id,
+        ready_at: rewards_ready_at,
+        ..withdrawal
+    };
+
+    WITHDRAWAL_READY.save(deps.storage, &Some(withdrawal))?;
+
+    Ok(rewards_ready_at)
+}
+
+// asks an address if it's a CW20 contract
+fn is_cw20(deps: Deps, address: &str) -> bool {
+    deps.api.addr_validate(address).is_ok()
+        && deps
+            .querier
+            .query_wasm_smart::<TokenInfoResponse>(address, &Cw20QueryMsg::TokenInfo {})
+            .is_ok()
+}
+
+// a withdrawal has to be for a supported native denom, or a CW20
+fn ensure_supported_asset(deps: Deps, denom_or_address: &str) -> Result<(), ContractError> {
+    if NATIVE_DENOMS.has(deps.storage, denom_or_address.to_string()) {
+        return Ok(());
+    }
+
+    // anything else has to be a CW20 contract
+    if is_cw20(deps, denom_or_address) {
+        Ok(())
+    } else {
+        Err(ContractError::UnsupportedDenom {
+            denom: denom_or_address.to_string(),
+        })
+    }
+}
+
+// works out who a withdrawal is paid out to
+// the recovery_address if it started the withdrawal
+// otherwise the withdraw_address
+fn withdrawal_recipient(
+    config: &Config,
+    withdrawal: &Option<Withdrawal>,
+) -> Result<Addr, ContractError> {
+    match withdrawal {
+        Some(wr) if wr.is_recovery => config
+            .recovery_address
+            .clone()
+            .ok_or(ContractError::RecoveryNotConfigured {}),
+        _ => Ok(config.withdraw_address.clone()),
+    }
+}
+
+// checks the sender can claim a ready withdrawal
+// this is the recipient, or anyone if permissionless execution is on
+// anyone else is owed a tip from the keeper pool, if there's enough in it
+fn ensure_can_claim(
+    storage: &dyn Storage,
+    config: &Config,
+    sender: &Addr,
+    recipient: &Addr,
+) -> Result<Option<Coin>, ContractError> {
+    if sender == recipient {
+        return Ok(None);
+    }
+    if !config.permissionless_execution {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let tip = match &config.keeper_tip {
+        Some(tip) if !tip.amount.is_zero() => tip,
+        _ => return Ok(None),
+    };
+    let pool = KEEPER_POOL
+        .may_load(storage, tip.denom.clone())?
+        .unwrap_or_default();
+    if pool < tip.amount {
+        return Ok(None);
+    }
+    Ok(Some(tip.clone()))
+}
 
-    // calc the balance
-    let updated_balance = match existing_balance {
-        Some(balance) => {
-            let new_balance: Uint128 = balance
-                .checked_add(wrapped.amount)
-                .map_err(|_| ContractError::CW20BalanceError {})?;
-            new_balance
+// takes a keeper's tip out of the keeper pool
+// this comes after the withdrawal's own checks, which leave the whole pool alone
+fn pay_keeper_tip(
+    storage: &mut dyn Storage,
+    keeper: &Addr,
+    tip: Option<Coin>,
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let tip = match tip {
+        Some(tip) => tip,
+        None => return Ok(None),
+    };
+    KEEPER_POOL.update(storage, tip.denom.clone(), |pool| -> StdResult<_> {
+        Ok(pool.unwrap_or_default().checked_sub(tip.amount)?)
+    })?;
+
+    Ok(Some(
+        BankMsg::Send {
+            to_address: keeper.to_string(),
+            amount: vec![tip],
         }
-        None => wrapped.amount,
+        .into(),
+    ))
+}
+
+// the delay on a vault
+// the default vault, or a name that was never set up, uses the withdraw delay
+fn vault_delay(storage: &dyn Storage, config: &Config, vault: &Option<String>) -> StdResult<u64> {
+    let delay_in_days = match vault {
+        Some(name) => VAULTS
+            .may_load(storage, name.clone())?
+            .map(|vault| vault.delay_in_days),
+        None => None,
     };
+    Ok(delay_in_days.unwrap_or(config.withdraw_delay_in_days))
+}
 
-    // update that bad boy
-    CW20_BALANCES.save(deps.storage, cw20_addr, &updated_balance)?;
+// checks a withdrawal won't take more than is in its vault
+// if no named vault or agreement holds any of the asset, it's all in the default vault
+// so there's nothing to check
+fn ensure_vault_balance(
+    deps: Deps,
+    env: &Env,
+    vault: &Option<String>,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if vault.is_none()
+        && allocated_to_vaults(deps.storage, denom_or_address)?.is_zero()
+        && pending_out_of_vault(deps.storage, None, denom_or_address)?.is_zero()
+        && committed_to_agreements(deps.storage, denom_or_address)?.is_zero()
+        && committed_to_streams(deps.storage, denom_or_address)?.is_zero()
+    {
+        return Ok(());
+    }
 
-    Ok(Response::new()
-        .add_attribute("method", "receive")
-        .add_attribute("balance", updated_balance))
+    let available = query_vault_balance(
+        deps,
+        &env.contract.address,
+        vault.as_deref(),
+        denom_or_address,
+    )?;
+    if amount > available {
+        return Err(ContractError::InsufficientVaultBalance {});
+    }
+    Ok(())
+}
+
+// checks taking an amount out of escrow leaves its reserve floor alone
+// anything already set aside doesn't count towards the floor
+fn ensure_above_reserve_floor(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let floor = config.reserve_floor(denom_or_address);
+    if !floor.is_zero() {
+        let balance = query_uncommitted_balance(deps, &env.contract.address, denom_or_address)?;
+        if amount > balance.saturating_sub(floor) {
+            return Err(ContractError::BelowReserveFloor { floor });
+        }
+    }
+    Ok(())
+}
+
+fn debit_vault(
+    storage: &mut dyn Storage,
+    vault: &Option<String>,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(name) = vault {
+        VAULT_BALANCES.update(
+            storage,
+            (denom_or_address.to_string(), name.clone()),
+            |balance| -> Result<_, ContractError> {
+                balance
+                    .unwrap_or_default()
+                    .checked_sub(amount)
+                    .map_err(|_| ContractError::InsufficientVaultBalance {})
+            },
+        )?;
+    }
+    Ok(())
+}
+
+// takes an evacuated amount of an asset out of the vaults
+// the default vault goes first, then named vaults from the hottest to the coldest
+// already is what this evacuation has taken of the asset so far
+fn debit_evacuated(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    denom_or_address: &str,
+    already: Uint128,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    // nothing has actually left yet, so the balance still counts what was already taken
+    let in_default =
+        query_vault_balance(deps.as_ref(), &env.contract.address, None, denom_or_address)?
+            .saturating_sub(already);
+    let mut remaining = amount.saturating_sub(in_default);
+    if remaining.is_zero() {
+        return Ok(());
+    }
+
+    let mut vaults = vec![];
+    for item in VAULT_BALANCES.prefix(denom_or_address.to_string()).range(
+        deps.storage,
+        None,
+        None,
+        Order::Ascending,
+    ) {
+        let (name, balance) = item?;
+        let delay_in_days = vault_delay(deps.storage, config, &Some(name.clone()))?;
+        vaults.push((delay_in_days, name, balance));
+    }
+    vaults.sort();
+
+    // a vault left without enough for its pending transfers loses them
+    let mut short = vec![];
+    for (_, name, balance) in vaults {
+        if remaining.is_zero() {
+            break;
+        }
+        let taken = balance.min(remaining);
+        remaining -= taken;
+        let left = balance - taken;
+        let key = (denom_or_address.to_string(), name.clone());
+        if left.is_zero() {
+            VAULT_BALANCES.remove(deps.storage, key);
+        } else {
+            VAULT_BALANCES.save(deps.storage, key, &left)?;
+        }
+        if left < pending_out_of_vault(deps.storage, Some(&name), denom_or_address)? {
+            short.push(Some(name));
+        }
+    }
+    // anything still left came out of what was waiting to leave the default vault
+    if !remaining.is_zero() {
+        short.push(None);
+    }
+
+    let mut transfer_ids = vec![];
+    for item in VAULT_TRANSFERS.range(deps.storage, None, None, Order::Ascending) {
+        let (id, transfer) = item?;
+        if transfer.denom_or_address == denom_or_address && short.contains(&transfer.from) {
+            transfer_ids.push(id);
+        }
+    }
+    for id in transfer_ids {
+        VAULT_TRANSFERS.remove(deps.storage, id);
+    }
+    Ok(())
+}
+
+fn credit_vault(
+    storage: &mut dyn Storage,
+    vault: &Option<String>,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    if let Some(name) = vault {
+        VAULT_BALANCES.update(
+            storage,
+            (denom_or_address.to_string(), name.clone()),
+            |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(amount)?) },
+        )?;
+    }
+    Ok(())
+}
+
+// checks a withdrawal fits in what's left of a category's budget
+// and returns the period it's spent in
+fn check_budget(
+    storage: &dyn Storage,
+    env: &Env,
+    name: &str,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<u64, ContractError> {
+    let category = BUDGET_CATEGORIES
+        .may_load(storage, name.to_string())?
+        .ok_or_else(|| ContractError::BudgetCategoryNotFound {
+            name: name.to_string(),
+        })?;
+    let period = category.period(env.block.time)?;
+
+    // a new withdrawal replaces any pending one, so only what's been spent counts
+    let spent = budget_spent(storage, name, denom_or_address, period)?;
+    let remaining = category.allocation(denom_or_address).saturating_sub(spent);
+    if amount > remaining {
+        return Err(ContractError::BudgetExceeded { remaining });
+    }
+    Ok(period)
+}
+
+// records a claimed withdrawal against the period it was started in
+fn record_budget_spend(storage: &mut dyn Storage, env: &Env, wr: &Withdrawal) -> StdResult<()> {
+    if let Some(name) = &wr.category {
+        // a removed category has nothing left to count it against
+        let category = match BUDGET_CATEGORIES.may_load(storage, name.clone())? {
+            Some(category) => category,
+            None => return Ok(()),
+        };
+        // if the period was lengthened since this started
+        // the old period is ahead of the current one, so it counts against this one
+        let period = wr.budget_period.min(category.period(env.block.time)?);
+
+        let key = (name.clone(), wr.denom_or_address.clone());
+        let spend = match BUDGET_SPEND.may_load(storage, key.clone())? {
+            // that period's already over, so there's nothing left to count it against
+            Some(spend) if spend.period > period => return Ok(()),
+            Some(spend) if spend.period == period => BudgetSpend {
+                period: spend.period,
+                amount: spend.amount.checked_add(wr.amount)?,
+            },
+            _ => BudgetSpend {
+                period,
+                amount: wr.amount,
+            },
+        };
+        BUDGET_SPEND.save(storage, key, &spend)?;
+    }
+    Ok(())
+}
+
+// throws out what a category has spent of every asset
+fn clear_budget_spend(storage: &mut dyn Storage, name: &str) -> StdResult<()> {
+    let denoms = BUDGET_SPEND
+        .prefix(name.to_string())
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for denom in denoms {
+        BUDGET_SPEND.remove(storage, (name.to_string(), denom));
+    }
+    Ok(())
 }
 
 // this sets the withdraw delay
 // note that it does not withdraw funds immediately
+#[allow(clippy::too_many_arguments)]
 pub fn start_withdraw(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     denom_or_address: String,
     amount: Uint128,
+    auto_undelegate: bool,
+    use_reserve_delay: bool,
+    vault: Option<String>,
+    category: Option<String>,
 ) -> Result<Response, ContractError> {
     // get config
     let config = CONFIG.load(deps.storage)?;
-    let withdraw_address = config.withdraw_address;
 
     // before continuing, only withdraw_address can call this
     ensure_eq!(
         info.sender,
-        withdraw_address,
+        config.withdraw_address,
         ContractError::Unauthorized {}
     );
 
-    // get number of days delay
-    let delay_in_days: u64 = config.withdraw_delay_in_days;
+    ensure_supported_asset(deps.as_ref(), &denom_or_address)?;
+
+    // each vault has its own delay and limits
+    let vault_delay_in_days = vault_delay(deps.storage, &config, &vault)?;
+    if let Some(name) = &vault {
+        let limit = VAULTS
+            .may_load(deps.storage, name.clone())?
+            .and_then(|vault| vault.limit(&denom_or_address));
+        if let Some(limit) = limit {
+            if amount > limit {
+                return Err(ContractError::VaultLimitExceeded { limit });
+            }
+        }
+    }
+    ensure_vault_balance(deps.as_ref(), &env, &vault, &denom_or_address, amount)?;
+
+    // spending in a category has to fit in what's left of its budget
+    let budget_period = match &category {
+        Some(name) => check_budget(deps.storage, &env, name, &denom_or_address, amount)?,
+        None => 0,
+    };
+
+    // dipping into the reserve needs the longer delay
+    let delay_in_days = if use_reserve_delay {
+        config
+            .reserve_delay_in_days
+            .ok_or(ContractError::ReserveDelayNotConfigured {})?
+            .max(vault_delay_in_days)
+    } else {
+        vault_delay_in_days
+    };
+
+    // free up any staked funds the withdrawal is going to need
+    let undelegate_msgs =
+        if auto_undelegate && denom_or_address == deps.querier.query_bonded_denom()? {
+            undelegate_shortfall(deps.as_ref(), &env, &denom_or_address, amount)?
+        } else {
+            vec![]
+        };
+
+    // starting a withdrawal also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let rewards_ready_at = save_withdrawal_with_delay(
+        deps,
+        &env,
+        &config,
+        delay_in_days,
+        Withdrawal {
+            id: 0,
+            ready_at: env.block.time,
+            denom_or_address,
+            amount,
+            is_recovery: false,
+            nft_token_ids: None,
+            vault: vault.clone(),
+            category: category.clone(),
+            budget_period,
+            use_reserve_delay,
+        },
+    )?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "start_withdraw")
+        .add_attribute("withdrawal_ready_timestamp", rewards_ready_at.to_string())
+        .add_attribute("use_reserve_delay", use_reserve_delay.to_string());
+    if let Some(vault) = vault {
+        response = response.add_attribute("vault", vault);
+    }
+    if let Some(category) = category {
+        response = response.add_attribute("category", category);
+    }
+
+    // the funds won't be liquid in time if unbonding takes longer than the delay
+    let unbonds_late = matches!(
+        config.unbonding_period_in_days,
+        Some(days) if days > delay_in_days
+    );
+    if !undelegate_msgs.is_empty() && unbonds_late {
+        response = response.add_attribute(
+            "warning",
+            "unbonding period is longer than the withdraw delay",
+        );
+    }
+
+    Ok(response
+        .add_attribute("undelegations", undelegate_msgs.len().to_string())
+        .add_messages(undelegate_msgs))
+}
 
-    // do some really simple maths
-    let seconds_in_day = 86400u64;
-    let delay_in_seconds = delay_in_days * seconds_in_day;
+// undelegates whatever the liquid balance is short of a withdrawal
+// taking from the biggest delegations first
+// the keeper pool isn't ours to withdraw, so it doesn't count as liquid
+fn undelegate_shortfall(
+    deps: Deps,
+    env: &Env,
+    denom: &str,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let keeper_pool = KEEPER_POOL
+        .may_load(deps.storage, denom.to_string())?
+        .unwrap_or_default();
+    let liquid = deps
+        .querier
+        .query_balance(&env.contract.address, denom)?
+        .amount
+        .saturating_sub(keeper_pool);
+    let mut shortfall = amount.saturating_sub(liquid);
+
+    let mut delegations = deps.querier.query_all_delegations(&env.contract.address)?;
+    delegations.sort_by_key(|delegation| Reverse(delegation.amount.amount));
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for delegation in delegations {
+        if shortfall.is_zero() {
+            break;
+        }
+        let undelegate_amount = shortfall.min(delegation.amount.amount);
+        shortfall -= undelegate_amount;
+        msgs.push(
+            StakingMsg::Undelegate {
+                validator: delegation.validator,
+                amount: Coin {
+                    denom: denom.to_string(),
+                    amount: undelegate_amount,
+                },
+            }
+            .into(),
+        );
+    }
 
-    // when is 'now'?
-    let now: Timestamp = env.block.time;
+    // there isn't enough staked to cover it either
+    if !shortfall.is_zero() {
+        return Err(ContractError::InsufficientContractBalance {});
+    }
 
-    // calculate now + configured days (in seconds)
-    let rewards_ready_at: Timestamp = now.plus_seconds(delay_in_seconds);
+    Ok(msgs)
+}
 
-    let withdrawal = Withdrawal {
-        ready_at: rewards_ready_at,
-        denom_or_address,
-        amount,
-    };
+// the withdraw_address or override_address checks in,
+// resetting its inactivity timer
+pub fn heartbeat(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    WITHDRAWAL_READY.save(deps.storage, &Some(withdrawal))?;
+    let is_withdraw_address = info.sender == config.withdraw_address;
+    let is_override_address = Some(&info.sender) == config.override_address.as_ref();
+
+    // only withdraw_address or override_address can call this
+    if !is_withdraw_address && !is_override_address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut recovery_cancelled = false;
+
+    if is_withdraw_address {
+        LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+        // we're alive after all, so a recovery is no longer needed
+        let withdrawal = WITHDRAWAL_READY.may_load(deps.storage)?.flatten();
+        recovery_cancelled = matches!(withdrawal, Some(wr) if wr.is_recovery);
+        if recovery_cancelled {
+            WITHDRAWAL_READY.save(deps.storage, &None)?;
+        }
+    }
+
+    // this also takes the powers back from any fallback
+    if is_override_address {
+        LAST_OVERRIDE_HEARTBEAT.save(deps.storage, &env.block.time)?;
+    }
 
     Ok(Response::new()
-        .add_attribute("action", "start_withdraw")
-        .add_attribute("withdrawal_ready_timestamp", rewards_ready_at.to_string()))
+        .add_attribute("action", "heartbeat")
+        .add_attribute("last_heartbeat", env.block.time.to_string())
+        .add_attribute("recovery_cancelled", recovery_cancelled.to_string()))
 }
 
-// this allows you to withdraw if the withdraw delay has passed
-pub fn execute_withdraw(
+// cancelling withdrawals and updating the addresses sit with the
+// fallback_override_address if the override_address has missed its check-in window
+// there are none left once the override role is renounced
+fn active_override_address(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+) -> Result<Option<Addr>, ContractError> {
+    let last_heartbeat = LAST_OVERRIDE_HEARTBEAT.load(storage)?;
+    Ok(config.active_override_address(last_heartbeat, env.block.time))
+}
+
+// if the withdraw_address has gone quiet for long enough
+// the recovery_address can start a delayed withdrawal to itself
+pub fn start_recovery_withdraw(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    denom: String,
+    denom_or_address: String,
+    amount: Uint128,
 ) -> Result<Response, ContractError> {
-    // get withdraw address
     let config = CONFIG.load(deps.storage)?;
-    let withdraw_address = config.withdraw_address;
 
-    // before continuing, only withdraw_address can call this
+    // is recovery even a thing for this contract?
+    let recovery_address = config
+        .recovery_address
+        .clone()
+        .ok_or(ContractError::RecoveryNotConfigured {})?;
+
+    // only recovery_address can call this
     ensure_eq!(
         info.sender,
-        withdraw_address,
+        recovery_address,
         ContractError::Unauthorized {}
     );
 
-    // get rewards ready timestamp
+    // has the withdraw_address been silent for long enough?
+    let last_heartbeat = LAST_WITHDRAW_HEARTBEAT.load(deps.storage)?;
+    match config.recovery_available_at(last_heartbeat) {
+        Some(available_at) if env.block.time > available_at => (),
+        _ => return Err(ContractError::WithdrawAddressStillActive {}),
+    }
+
+    ensure_supported_asset(deps.as_ref(), &denom_or_address)?;
+
+    let rewards_ready_at =
+        save_withdrawal(deps, &env, &config, denom_or_address, amount, true, None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_recovery_withdraw")
+        .add_attribute("recovery_address", recovery_address)
+        .add_attribute("withdrawal_ready_timestamp", rewards_ready_at.to_string()))
+}
+
+// this allows you to withdraw if the withdraw delay has passed
+pub fn execute_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+) -> Result<Response, ContractError> {
+    // get withdraw address
+    let config = CONFIG.load(deps.storage)?;
     let withdrawal_ready = WITHDRAWAL_READY.load(deps.storage)?;
+    let withdraw_address = withdrawal_recipient(&config, &withdrawal_ready)?;
+
+    // before continuing, only the recipient can call this
+    // i.e. the withdraw_address, or the recovery_address if it started this
+    // unless anyone is allowed to
+    let keeper_tip = ensure_can_claim(deps.storage, &config, &info.sender, &withdraw_address)?;
 
     if let Some(wr) = withdrawal_ready {
         // check if we are after that time
@@ -125,6 +946,33 @@ pub fn execute_withdraw(
                     wr.denom_or_address,
                     ContractError::WithdrawalDenomMismatch {}
                 );
+                if wr.nft_token_ids.is_some() {
+                    return Err(ContractError::WithdrawalDenomMismatch {});
+                }
+
+                // some of it could still be unbonding
+                // and the keeper pool isn't ours to withdraw
+                let keeper_pool = KEEPER_POOL
+                    .may_load(deps.storage, denom.clone())?
+                    .unwrap_or_default();
+                let liquid = deps
+                    .querier
+                    .query_balance(&env.contract.address, &denom)?
+                    .amount
+                    .saturating_sub(keeper_pool);
+                if liquid < wr.amount {
+                    return Err(ContractError::InsufficientContractBalance {});
+                }
+
+                // a reserve floor could have been raised in the meantime
+                if !wr.use_reserve_delay {
+                    ensure_above_reserve_floor(deps.as_ref(), &env, &config, &denom, wr.amount)?;
+                }
+
+                // funds could have moved into a colder vault in the meantime
+                ensure_vault_balance(deps.as_ref(), &env, &wr.vault, &denom, wr.amount)?;
+                debit_vault(deps.storage, &wr.vault, &denom, wr.amount)?;
+                record_budget_spend(deps.storage, &env, &wr)?;
 
                 // set up a bank send to the withdraw address
                 // from this contract
@@ -140,11 +988,13 @@ pub fn execute_withdraw(
 
                 // reset the timer now we've claimed the withdrawal
                 WITHDRAWAL_READY.save(deps.storage, &None)?;
+                let keeper_tip = pay_keeper_tip(deps.storage, &info.sender, keeper_tip)?;
 
                 Ok(Response::new()
                     .add_attribute("action", "execute_withdraw")
                     .add_attribute("withdraw_address", withdraw_address)
-                    .add_messages(msgs))
+                    .add_messages(msgs)
+                    .add_messages(keeper_tip))
             }
             false => Err(ContractError::WithdrawalNotReady {}),
         }
@@ -156,6 +1006,9 @@ pub fn execute_withdraw(
 // this calls the address passed in, a CW20 contract,
 // and gets it to transfer the balance assigned to this contract
 // to the withdraw_address
+// the escrowed balance in the ledger is debited first
+// and any untracked surplus second, so the ledger never overstates
+// what we hold
 pub fn execute_cw20_withdraw(
     deps: DepsMut,
     env: Env,
@@ -164,22 +1017,21 @@ pub fn execute_cw20_withdraw(
 ) -> Result<Response, ContractError> {
     // get withdraw address
     let config = CONFIG.load(deps.storage)?;
-    let withdraw_address = config.withdraw_address;
+    let withdrawal_ready = WITHDRAWAL_READY.load(deps.storage)?;
+    let withdraw_address = withdrawal_recipient(&config, &withdrawal_ready)?;
 
-    // before continuing, only withdraw_address can call this
-    ensure_eq!(
-        info.sender,
-        withdraw_address,
-        ContractError::Unauthorized {}
-    );
+    // before continuing, only the recipient can call this
+    // i.e. the withdraw_address, or the recovery_address if it started this
+    // unless anyone is allowed to
+    let keeper_tip = ensure_can_claim(deps.storage, &config, &info.sender, &withdraw_address)?;
+
+    // note that we don't check enable_cw20_receive here
+    // turning off receive should not strand what's already escrowed
 
     // check the address we've been passed is kosher
     let validated_cw20_addr = deps.api.addr_validate(&address)?;
 
-    // now we can get rewards ready timestamp
-    // and see if we can send those tasty tasty cw20s
-    let withdrawal_ready = WITHDRAWAL_READY.load(deps.storage)?;
-
+    // now we can see if we can send those tasty tasty cw20s
     if let Some(wr) = withdrawal_ready {
         // check if we are after that time
         let withdrawal_claimable = env.block.time > wr.ready_at;
@@ -194,8 +1046,41 @@ pub fn execute_cw20_withdraw(
                     validated_requested_cw20_addr,
                     ContractError::WithdrawalCW20Mismatch {}
                 );
+                if wr.nft_token_ids.is_some() {
+                    return Err(ContractError::WithdrawalCW20Mismatch {});
+                }
+
+                // a reserve floor could have been raised in the meantime
+                if !wr.use_reserve_delay {
+                    ensure_above_reserve_floor(
+                        deps.as_ref(),
+                        &env,
+                        &config,
+                        &wr.denom_or_address,
+                        wr.amount,
+                    )?;
+                }
+
+                // funds could have moved into a colder vault in the meantime
+                ensure_vault_balance(
+                    deps.as_ref(),
+                    &env,
+                    &wr.vault,
+                    &wr.denom_or_address,
+                    wr.amount,
+                )?;
+                debit_vault(deps.storage, &wr.vault, &wr.denom_or_address, wr.amount)?;
+                record_budget_spend(deps.storage, &env, &wr)?;
+
+                let (tracked_amount, untracked_amount) = debit_cw20(
+                    deps.storage,
+                    &deps.querier,
+                    &env,
+                    &validated_cw20_addr,
+                    wr.amount,
+                )?;
 
-                // put together msg
+                // call the cw20 and transfer the balance to withdraw_address
                 let msg = WasmMsg::Execute {
                     contract_addr: validated_cw20_addr.to_string(),
                     msg: to_binary(&Cw20ExecuteMsg::Transfer {
@@ -207,11 +1092,15 @@ pub fn execute_cw20_withdraw(
 
                 // reset the timer now we've claimed the withdrawal
                 WITHDRAWAL_READY.save(deps.storage, &None)?;
+                let keeper_tip = pay_keeper_tip(deps.storage, &info.sender, keeper_tip)?;
 
                 Ok(Response::new()
                     .add_attribute("action", "execute_withdraw")
                     .add_attribute("withdraw_address", withdraw_address)
-                    .add_message(msg))
+                    .add_attribute("tracked_amount", tracked_amount)
+                    .add_attribute("untracked_amount", untracked_amount)
+                    .add_message(msg)
+                    .add_messages(keeper_tip))
             }
             false => Err(ContractError::WithdrawalNotReady {}),
         }
@@ -220,125 +1109,201 @@ pub fn execute_cw20_withdraw(
     }
 }
 
-// this looks up a CW20 balance in the balances map
-// if found, it clears the balance, and sends to the nominated address
-// by calling the CW20 contract in question
-pub fn execute_escrow_cw20_withdraw(
+// takes an amount of a CW20 out of escrow
+// the escrowed balance in the ledger is debited first
+// and any untracked surplus second
+// returns the tracked and untracked amounts
+fn debit_cw20(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    cw20_addr: &Addr,
+    amount: Uint128,
+) -> Result<(Uint128, Uint128), ContractError> {
+    // split the amount into what's tracked and what isn't
+    let ledger_balance = CW20_BALANCES.may_load(storage, cw20_addr.clone())?;
+    let tracked_amount = amount.min(ledger_balance.unwrap_or_default());
+    let untracked_amount = amount - tracked_amount;
+
+    // anything untracked has to come out of the surplus
+    if !untracked_amount.is_zero() {
+        let check = check_cw20_balance(
+            querier,
+            cw20_addr,
+            &env.contract.address,
+            ledger_balance.unwrap_or_default(),
+        )?;
+        if untracked_amount > check.surplus {
+            return Err(ContractError::InsufficientContractBalance {});
+        }
+    }
+
+    // then subtract from our internal treasury
+    if let Some(balance) = ledger_balance {
+        CW20_BALANCES.save(storage, cw20_addr.clone(), &(balance - tracked_amount))?;
+    }
+
+    Ok((tracked_amount, untracked_amount))
+}
+
+pub fn override_withdraw(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    address: String,
 ) -> Result<Response, ContractError> {
-    // get withdraw address
+    // get override address
     let config = CONFIG.load(deps.storage)?;
-    let withdraw_address = config.withdraw_address;
+    let override_address = active_override_address(deps.storage, &env, &config)?
+        .ok_or(ContractError::OverrideRenounced {})?;
 
-    // before continuing, only withdraw_address can call this
-    ensure_eq!(
-        info.sender,
-        withdraw_address,
+    // explicitly crash out if no withdrawal exists
+    let withdrawal = WITHDRAWAL_READY.may_load(deps.storage)?;
+    if withdrawal.is_none() {
+        return Err(ContractError::WithdrawalNotRequested {});
+    }
+
+    // before continuing, only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
         ContractError::Unauthorized {}
     );
 
-    // check that config is set to true
-    let receive_enabled = config.enable_cw20_receive;
-    ensure_eq!(receive_enabled, true, ContractError::CW20ReceiveDisabled {});
-
-    // check the address we've been passed is kosher
-    let validated_cw20_addr = deps.api.addr_validate(&address)?;
+    WITHDRAWAL_READY.save(deps.storage, &None)?;
 
-    // check if there's any balance
-    let cw20_balance = CW20_BALANCES.load(deps.storage, validated_cw20_addr.clone())?;
+    Ok(Response::new().add_attribute("action", "override_withdraw"))
+}
 
-    // now we can get rewards ready timestamp
-    // and see if we can send those tasty tasty cw20s
-    let withdrawal_ready = WITHDRAWAL_READY.load(deps.storage)?;
+// cancels a withdrawal with a signature from the guardian_key
+// so a cold key can cancel without having to pay gas
+pub fn override_withdraw_with_signature(
+    deps: DepsMut,
+    env: Env,
+    withdrawal_id: u64,
+    nonce: u64,
+    signature: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let guardian_key = config
+        .guardian_key
+        .ok_or(ContractError::GuardianKeyNotConfigured {})?;
 
-    if let Some(wr) = withdrawal_ready {
-        // check if we are after that time
-        let withdrawal_claimable = env.block.time > wr.ready_at;
+    // explicitly crash out if no withdrawal exists
+    let withdrawal = WITHDRAWAL_READY
+        .may_load(deps.storage)?
+        .flatten()
+        .ok_or(ContractError::WithdrawalNotRequested {})?;
 
-        // dispatch Response or ContractError
-        match withdrawal_claimable {
-            true => {
-                // ok let's check this is what we expect before continuing
-                // this is effectively denom
-                let validated_requested_cw20_addr = deps.api.addr_validate(&wr.denom_or_address)?;
-                ensure_eq!(
-                    validated_cw20_addr,
-                    validated_requested_cw20_addr,
-                    ContractError::WithdrawalCW20Mismatch {}
-                );
+    // the signature is for this withdrawal, and no other
+    ensure_eq!(
+        withdrawal.id,
+        withdrawal_id,
+        ContractError::WithdrawalIdMismatch {}
+    );
 
-                // first, find new balance
-                let new_balance = cw20_balance
-                    .checked_sub(wr.amount)
-                    .map_err(|_| ContractError::CW20BalanceError {})?;
+    // and can't be replayed
+    if let Some(last_nonce) = LAST_GUARDIAN_NONCE.may_load(deps.storage)? {
+        if nonce <= last_nonce {
+            return Err(ContractError::NonceAlreadyUsed {});
+        }
+    }
 
-                // call the cw20 and transfer the balance to withdraw_address
-                let msg = WasmMsg::Execute {
-                    contract_addr: validated_cw20_addr.to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                        recipient: withdraw_address.to_string(),
-                        amount: wr.amount,
-                    })?,
-                    funds: vec![],
-                };
+    let digest = guardian_cancellation_digest(
+        &env.block.chain_id,
+        &env.contract.address,
+        withdrawal_id,
+        nonce,
+    );
+    let verified = match guardian_key.algorithm {
+        SignatureAlgorithm::Secp256k1 => {
+            deps.api
+                .secp256k1_verify(&digest, &signature, &guardian_key.public_key)
+        }
+        SignatureAlgorithm::Ed25519 => {
+            deps.api
+                .ed25519_verify(&digest, &signature, &guardian_key.public_key)
+        }
+    }
+    // a malformed signature is just as bad as a wrong one
+    .unwrap_or(false);
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
 
-                // then subtract from our internal treasury
-                CW20_BALANCES.save(deps.storage, validated_cw20_addr, &new_balance)?;
+    LAST_GUARDIAN_NONCE.save(deps.storage, &nonce)?;
+    WITHDRAWAL_READY.save(deps.storage, &None)?;
 
-                // reset the timer now we've claimed the withdrawal
-                WITHDRAWAL_READY.save(deps.storage, &None)?;
+    Ok(Response::new()
+        .add_attribute("action", "override_withdraw_with_signature")
+        .add_attribute("withdrawal_id", withdrawal_id.to_string())
+        .add_attribute("nonce", nonce.to_string()))
+}
 
-                Ok(Response::new()
-                    .add_attribute("action", "execute_withdraw")
-                    .add_attribute("withdraw_address", withdraw_address)
-                    .add_message(msg))
-            }
-            false => Err(ContractError::WithdrawalNotReady {}),
-        }
-    } else {
-        Err(ContractError::WithdrawalNotRequested {})
+fn ensure_unlocked(config: &Config, field: LockableField) -> Result<(), ContractError> {
+    if config.is_locked(&field) {
+        return Err(ContractError::FieldLocked { field });
     }
+    Ok(())
 }
 
-pub fn override_withdraw(
+// locks part of the config for good
+pub fn lock_field(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    field: LockableField,
 ) -> Result<Response, ContractError> {
-    // get override address
     let config = CONFIG.load(deps.storage)?;
-    let override_address = config.override_address;
+    let override_address = config.override_address.clone();
 
-    // explicitly crash out if no withdrawal exists
-    let withdrawal = WITHDRAWAL_READY.may_load(deps.storage)?;
-    if withdrawal.is_none() {
-        return Err(ContractError::WithdrawalNotRequested {});
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
     }
 
-    // before continuing, only override_address can call this
-    ensure_eq!(
-        info.sender,
-        override_address,
-        ContractError::Unauthorized {}
+    // locking an address takes away the override's power to replace it
+    // so only the override side can do that
+    let is_address = matches!(
+        field,
+        LockableField::WithdrawAddress | LockableField::OverrideAddress
     );
+    if is_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
 
-    WITHDRAWAL_READY.save(deps.storage, &None)?;
+    let response = Response::new()
+        .add_attribute("action", "lock_field")
+        .add_attribute("field", field.to_string())
+        .add_attribute("locked_by", info.sender);
 
-    Ok(Response::new().add_attribute("action", "override_withdraw"))
+    // locking twice does nothing
+    if config.is_locked(&field) {
+        return Ok(response);
+    }
+
+    let mut locked_fields = config.locked_fields.clone();
+    locked_fields.push(field);
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            locked_fields,
+            ..config
+        },
+    )?;
+
+    Ok(response)
 }
 
 pub fn update_override_address(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     address: String,
 ) -> Result<Response, ContractError> {
     // get override address
     let config = CONFIG.load(deps.storage)?;
-    let override_address = config.override_address;
+    let override_address = active_override_address(deps.storage, &env, &config)?
+        .ok_or(ContractError::OverrideRenounced {})?;
 
     // before continuing, only override_address can call this
     ensure_eq!(
@@ -347,42 +1312,138 @@ pub fn update_override_address(
         ContractError::Unauthorized {}
     );
 
-    // then check config
-    ensure_eq!(
-        config.set_override_as_immutable,
-        false,
-        ContractError::OverrideAddressIsImmutable {}
-    );
+    // then check it hasn't been locked
+    ensure_unlocked(&config, LockableField::OverrideAddress)?;
 
     let new_override_address = deps.api.addr_validate(&address)?;
 
+    // the safe_harbor_address can't hold the override powers
+    if Some(&new_override_address) == config.safe_harbor_address.as_ref() {
+        return Err(ContractError::InvalidSafeHarbor {});
+    }
+
     // update
     let new_config = Config {
-        override_address: new_override_address.clone(),
-        withdraw_address: config.withdraw_address,
-        withdraw_delay_in_days: config.withdraw_delay_in_days,
-        native_denom: config.native_denom,
-        set_withdraw_as_immutable: config.set_withdraw_as_immutable,
-        set_override_as_immutable: config.set_override_as_immutable,
-        enable_cw20_receive: config.enable_cw20_receive,
+        override_address: Some(new_override_address.clone()),
+        ..config
     };
 
     CONFIG.save(deps.storage, &new_config)?;
 
+    // the new override_address gets a full check-in window
+    LAST_OVERRIDE_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    // and has to start its own renounce, if it wants one
+    PENDING_RENOUNCE.save(deps.storage, &None)?;
+
+    // or config update
+    let config_update_cancelled = clear_pending_config_update(deps.storage)?;
+
     Ok(Response::new()
         .add_attribute("action", "update_override_address")
-        .add_attribute("new_override_address", new_override_address))
+        .add_attribute("new_override_address", new_override_address)
+        .add_attribute(
+            "config_update_cancelled",
+            config_update_cancelled.to_string(),
+        ))
 }
 
-pub fn update_withdrawal_address(
+// the first call starts the renounce, and a second call
+// once the withdraw delay has passed gives up the override role for good
+pub fn renounce_override(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    // giving it up is a change too
+    ensure_unlocked(&config, LockableField::OverrideAddress)?;
+
+    let ready_at = match PENDING_RENOUNCE.may_load(deps.storage)?.flatten() {
+        Some(ready_at) => ready_at,
+        None => {
+            // same delay as a withdrawal
+            let ready_at = days_after(env.block.time, config.withdraw_delay_in_days)?;
+            PENDING_RENOUNCE.save(deps.storage, &Some(ready_at))?;
+
+            return Ok(Response::new()
+                .add_attribute("action", "start_renounce_override")
+                .add_attribute("ready_at", ready_at.to_string()));
+        }
+    };
+
+    if env.block.time <= ready_at {
+        return Err(ContractError::RenounceNotReady {});
+    }
+
+    // the fallback and guardian_key are override powers too
+    CONFIG.save(
+        deps.storage,
+        &Config {
+            override_address: None,
+            fallback_override_address: None,
+            guardian_key: None,
+            ..config
+        },
+    )?;
+    PENDING_RENOUNCE.save(deps.storage, &None)?;
+
+    // nobody is left to apply this
+    PENDING_CONFIG_UPDATE.save(deps.storage, &None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "renounce_override")
+        .add_attribute("renounced_by", info.sender))
+}
+
+// either role can throw out a pending renounce
+pub fn cancel_renounce_override(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // explicitly crash out if no renounce exists
+    if PENDING_RENOUNCE.may_load(deps.storage)?.flatten().is_none() {
+        return Err(ContractError::RenounceNotRequested {});
+    }
+
+    PENDING_RENOUNCE.save(deps.storage, &None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_renounce_override")
+        .add_attribute("cancelled_by", info.sender))
+}
+
+pub fn update_withdrawal_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
     address: String,
 ) -> Result<Response, ContractError> {
     // get override address
     let config = CONFIG.load(deps.storage)?;
-    let override_address = config.override_address;
+    let override_address = active_override_address(deps.storage, &env, &config)?
+        .ok_or(ContractError::OverrideRenounced {})?;
 
     // before continuing, only override_address can call this
     ensure_eq!(
@@ -392,31 +1453,2367 @@ pub fn update_withdrawal_address(
     );
 
     // but wait! can this even be changed?
-    let is_immutable = config.set_withdraw_as_immutable;
-    // if the above is false, then continue
-    // otherwise error
-    ensure_eq!(
-        is_immutable,
-        false,
-        ContractError::WithdrawalAddressIsImmutable {}
-    );
+    ensure_unlocked(&config, LockableField::WithdrawAddress)?;
 
     let new_withdraw_address = deps.api.addr_validate(&address)?;
 
     // LFG, change it
     let new_config = Config {
-        override_address,
         withdraw_address: new_withdraw_address.clone(),
-        withdraw_delay_in_days: config.withdraw_delay_in_days,
-        native_denom: config.native_denom,
-        set_withdraw_as_immutable: config.set_withdraw_as_immutable,
-        set_override_as_immutable: config.set_override_as_immutable,
-        enable_cw20_receive: config.enable_cw20_receive,
+        ..config
     };
 
     CONFIG.save(deps.storage, &new_config)?;
 
+    // the new withdraw_address gets a full inactivity period
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    // anything pending was proposed with the old address in mind
+    let config_update_cancelled = clear_pending_config_update(deps.storage)?;
+
     Ok(Response::new()
         .add_attribute("action", "update_withdrawal_address")
-        .add_attribute("new_withdraw_address", new_withdraw_address))
+        .add_attribute("new_withdraw_address", new_withdraw_address)
+        .add_attribute(
+            "config_update_cancelled",
+            config_update_cancelled.to_string(),
+        ))
+}
+
+// throws out any pending config update
+// returns whether there was one
+fn clear_pending_config_update(storage: &mut dyn Storage) -> StdResult<bool> {
+    let pending = PENDING_CONFIG_UPDATE.may_load(storage)?.flatten().is_some();
+    PENDING_CONFIG_UPDATE.save(storage, &None)?;
+    Ok(pending)
+}
+
+// checks a config update can actually be applied
+// so we fail when it's proposed, not after the delay
+fn validate_config_update(
+    api: &dyn Api,
+    config: &Config,
+    update: &ConfigUpdate,
+) -> Result<(), ContractError> {
+    if let Some(field) = update.locked_by() {
+        ensure_unlocked(config, field)?;
+    }
+
+    match update {
+        ConfigUpdate::AddAcceptedCw20 { address }
+        | ConfigUpdate::RemoveAcceptedCw20 { address } => {
+            api.addr_validate(address)?;
+        }
+        ConfigUpdate::SetCw20Receive { .. } => (),
+        ConfigUpdate::AddNativeDenom { denom } => validate_native_denom(api, denom)?,
+        ConfigUpdate::RemoveNativeDenom { .. } => (),
+        ConfigUpdate::SetUnbondingPeriod { .. } => (),
+        ConfigUpdate::SetExecutionAllowList { allow_list } => {
+            if let Some(allow_list) = allow_list {
+                validate_execution_allow_list(api, allow_list)?;
+            }
+        }
+        ConfigUpdate::SetPermissionlessExecution { .. } => (),
+        ConfigUpdate::SetWithdrawDelay { days } => {
+            validate_delays(*days, config.reserve_delay_in_days)?;
+        }
+        ConfigUpdate::SetReserveFloor {
+            denom_or_address, ..
+        } => {
+            if denom_or_address.is_empty() {
+                return Err(ContractError::UnsupportedDenom {
+                    denom: denom_or_address.clone(),
+                });
+            }
+        }
+        ConfigUpdate::SetReserveDelay { days } => {
+            validate_delays(config.withdraw_delay_in_days, *days)?;
+        }
+        ConfigUpdate::SetDepositRefundPeriod { .. } => (),
+        ConfigUpdate::SetVault {
+            name,
+            delay_in_days,
+            ..
+        } => {
+            validate_vault_name(name)?;
+            validate_delay(*delay_in_days)?;
+        }
+        ConfigUpdate::RemoveVault { name } => validate_vault_name(name)?,
+        ConfigUpdate::SetBudgetCategory {
+            name,
+            period_in_days,
+            ..
+        } => {
+            validate_category_name(name)?;
+            if *period_in_days == 0 || *period_in_days > MAX_BUDGET_PERIOD_IN_DAYS {
+                return Err(ContractError::InvalidBudgetPeriod {});
+            }
+        }
+        ConfigUpdate::RemoveBudgetCategory { name } => {
+            validate_category_name(name)?;
+        }
+        ConfigUpdate::SetGuardianKey { key } => {
+            if let Some(key) = key {
+                validate_guardian_key(key)?;
+            }
+        }
+        ConfigUpdate::SetKeeperTip { tip } => {
+            if let Some(tip) = tip {
+                validate_native_denom(api, &tip.denom)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// proposes a config update, which can be applied
+// once the withdraw delay has passed
+pub fn start_config_update(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    update: ConfigUpdate,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    validate_config_update(deps.api, &config, &update)?;
+
+    // one at a time, so a pending update can't be quietly swapped out
+    if PENDING_CONFIG_UPDATE
+        .may_load(deps.storage)?
+        .flatten()
+        .is_some()
+    {
+        return Err(ContractError::ConfigUpdatePending {});
+    }
+
+    // same delay as a withdrawal
+    // but changing a vault waits out its own delay too
+    let delay_in_days = match &update {
+        ConfigUpdate::SetVault { name, .. } | ConfigUpdate::RemoveVault { name } => {
+            vault_delay(deps.storage, &config, &Some(name.clone()))?
+                .max(config.withdraw_delay_in_days)
+        }
+        _ => config.withdraw_delay_in_days,
+    };
+    let ready_at = days_after(env.block.time, delay_in_days)?;
+
+    PENDING_CONFIG_UPDATE.save(
+        deps.storage,
+        &Some(PendingConfigUpdate { ready_at, update }),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_config_update")
+        .add_attribute("config_update_ready_timestamp", ready_at.to_string()))
+}
+
+// applies the pending config update, if the delay has passed
+pub fn execute_config_update(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    let pending = PENDING_CONFIG_UPDATE
+        .may_load(deps.storage)?
+        .flatten()
+        .ok_or(ContractError::ConfigUpdateNotRequested {})?;
+
+    if env.block.time <= pending.ready_at {
+        return Err(ContractError::ConfigUpdateNotReady {});
+    }
+
+    // the field could have been locked in the meantime
+    if let Some(field) = pending.update.locked_by() {
+        ensure_unlocked(&config, field)?;
+    }
+
+    let response = Response::new().add_attribute("action", "execute_config_update");
+
+    let response = match pending.update {
+        ConfigUpdate::AddAcceptedCw20 { address } => {
+            let cw20_addr = deps.api.addr_validate(&address)?;
+            ACCEPTED_CW20S.save(deps.storage, cw20_addr.clone(), &Empty {})?;
+            response.add_attribute("accepted_cw20_added", cw20_addr)
+        }
+        ConfigUpdate::RemoveAcceptedCw20 { address } => {
+            let cw20_addr = deps.api.addr_validate(&address)?;
+            ACCEPTED_CW20S.remove(deps.storage, cw20_addr.clone());
+            response.add_attribute("accepted_cw20_removed", cw20_addr)
+        }
+        ConfigUpdate::SetCw20Receive { enabled } => {
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    enable_cw20_receive: enabled,
+                    ..config
+                },
+            )?;
+            response.add_attribute("enable_cw20_receive", enabled.to_string())
+        }
+        ConfigUpdate::AddNativeDenom { denom } => {
+            NATIVE_DENOMS.save(deps.storage, denom.clone(), &Empty {})?;
+            response.add_attribute("native_denom_added", denom)
+        }
+        ConfigUpdate::RemoveNativeDenom { denom } => {
+            NATIVE_DENOMS.remove(deps.storage, denom.clone());
+            response.add_attribute("native_denom_removed", denom)
+        }
+        ConfigUpdate::SetUnbondingPeriod { days } => {
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    unbonding_period_in_days: days,
+                    ..config
+                },
+            )?;
+            let days = days.map_or_else(|| "none".to_string(), |days| days.to_string());
+            response.add_attribute("unbonding_period_in_days", days)
+        }
+        ConfigUpdate::SetExecutionAllowList { allow_list } => {
+            let restricted = allow_list.is_some();
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    execution_allow_list: allow_list,
+                    ..config
+                },
+            )?;
+            response.add_attribute("execution_restricted", restricted.to_string())
+        }
+        ConfigUpdate::SetPermissionlessExecution { enabled } => {
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    permissionless_execution: enabled,
+                    ..config
+                },
+            )?;
+            response.add_attribute("permissionless_execution", enabled.to_string())
+        }
+        ConfigUpdate::SetKeeperTip { tip } => {
+            let tip_attribute = tip
+                .as_ref()
+                .map_or_else(|| "none".to_string(), |tip| tip.to_string());
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    keeper_tip: tip,
+                    ..config
+                },
+            )?;
+            response.add_attribute("keeper_tip", tip_attribute)
+        }
+        ConfigUpdate::SetGuardianKey { key } => {
+            let has_guardian_key = key.is_some();
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    guardian_key: key,
+                    ..config
+                },
+            )?;
+            response.add_attribute("guardian_key_set", has_guardian_key.to_string())
+        }
+        ConfigUpdate::SetWithdrawDelay { days } => {
+            // the reserve delay could have changed in the meantime
+            validate_delays(days, config.reserve_delay_in_days)?;
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    withdraw_delay_in_days: days,
+                    ..config
+                },
+            )?;
+            response.add_attribute("withdraw_delay", days.to_string())
+        }
+        ConfigUpdate::SetReserveFloor {
+            denom_or_address,
+            amount,
+        } => {
+            let mut config = config;
+            config.set_reserve_floor(denom_or_address.clone(), amount);
+            CONFIG.save(deps.storage, &config)?;
+            response
+                .add_attribute("reserve_floor_denom", denom_or_address)
+                .add_attribute("reserve_floor", amount)
+        }
+        ConfigUpdate::SetReserveDelay { days } => {
+            // the withdraw delay could have changed in the meantime
+            validate_delays(config.withdraw_delay_in_days, days)?;
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    reserve_delay_in_days: days,
+                    ..config
+                },
+            )?;
+            let days = days.map_or("none".to_string(), |days| days.to_string());
+            response.add_attribute("reserve_delay", days)
+        }
+        ConfigUpdate::SetDepositRefundPeriod { days } => {
+            CONFIG.save(
+                deps.storage,
+                &Config {
+                    deposit_refund_period_in_days: days,
+                    ..config
+                },
+            )?;
+            let days = days.map_or("none".to_string(), |days| days.to_string());
+            response.add_attribute("deposit_refund_period", days)
+        }
+        ConfigUpdate::SetVault {
+            name,
+            delay_in_days,
+            limits,
+        } => {
+            VAULTS.save(
+                deps.storage,
+                name.clone(),
+                &Vault {
+                    name: name.clone(),
+                    delay_in_days,
+                    limits,
+                },
+            )?;
+            response
+                .add_attribute("vault", name)
+                .add_attribute("vault_delay", delay_in_days.to_string())
+        }
+        ConfigUpdate::RemoveVault { name } => {
+            if !VAULTS.has(deps.storage, name.clone()) {
+                return Err(ContractError::VaultNotFound { name });
+            }
+            // anything left in it, or on its way in or out, would end up in the default vault
+            // balances are keyed by asset, but this is rare enough to look through them all
+            let mut emptied = vec![];
+            for item in VAULT_BALANCES.range(deps.storage, None, None, Order::Ascending) {
+                let ((denom, vault), balance) = item?;
+                if vault == name {
+                    if !balance.is_zero() {
+                        return Err(ContractError::VaultNotEmpty {});
+                    }
+                    emptied.push((denom, vault));
+                }
+            }
+            for item in VAULT_TRANSFERS.range(deps.storage, None, None, Order::Ascending) {
+                let (_, transfer) = item?;
+                if transfer.from.as_ref() == Some(&name) || transfer.to.as_ref() == Some(&name) {
+                    return Err(ContractError::VaultNotEmpty {});
+                }
+            }
+            for key in emptied {
+                VAULT_BALANCES.remove(deps.storage, key);
+            }
+            VAULTS.remove(deps.storage, name.clone());
+            response.add_attribute("vault_removed", name)
+        }
+        ConfigUpdate::SetBudgetCategory {
+            name,
+            period_in_days,
+            allocations,
+        } => {
+            // spending is counted in periods of the old length
+            // so a new length starts the count over
+            let existing = BUDGET_CATEGORIES.may_load(deps.storage, name.clone())?;
+            if matches!(existing, Some(category) if category.period_in_days != period_in_days) {
+                clear_budget_spend(deps.storage, &name)?;
+            }
+            BUDGET_CATEGORIES.save(
+                deps.storage,
+                name.clone(),
+                &BudgetCategory {
+                    name: name.clone(),
+                    period_in_days,
+                    allocations,
+                },
+            )?;
+            response
+                .add_attribute("budget_category", name)
+                .add_attribute("budget_period_in_days", period_in_days.to_string())
+        }
+        ConfigUpdate::RemoveBudgetCategory { name } => {
+            if !BUDGET_CATEGORIES.has(deps.storage, name.clone()) {
+                return Err(ContractError::BudgetCategoryNotFound { name });
+            }
+            BUDGET_CATEGORIES.remove(deps.storage, name.clone());
+            // so it starts from scratch if it's added back
+            clear_budget_spend(deps.storage, &name)?;
+            response.add_attribute("budget_category_removed", name)
+        }
+    };
+
+    PENDING_CONFIG_UPDATE.save(deps.storage, &None)?;
+
+    Ok(response)
+}
+
+// either role can throw out a pending config update
+pub fn cancel_config_update(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // explicitly crash out if no update exists
+    if PENDING_CONFIG_UPDATE
+        .may_load(deps.storage)?
+        .flatten()
+        .is_none()
+    {
+        return Err(ContractError::ConfigUpdateNotRequested {});
+    }
+
+    PENDING_CONFIG_UPDATE.save(deps.storage, &None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_config_update")
+        .add_attribute("cancelled_by", info.sender))
+}
+
+// sets the escrowed balance of a CW20 to what we actually hold
+// e.g. after a direct Transfer, or an untracked withdrawal
+pub fn sync_cw20_balance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    let cw20_addr = deps.api.addr_validate(&address)?;
+
+    // only start tracking CW20s we've agreed to hold
+    let ledger_balance = CW20_BALANCES.may_load(deps.storage, cw20_addr.clone())?;
+    if ledger_balance.is_none() && !ACCEPTED_CW20S.has(deps.storage, cw20_addr.clone()) {
+        return Err(ContractError::CW20TokenNotAccepted {});
+    }
+
+    let check = check_cw20_balance(
+        &deps.querier,
+        &cw20_addr,
+        &env.contract.address,
+        ledger_balance.unwrap_or_default(),
+    )?;
+
+    CW20_BALANCES.save(deps.storage, cw20_addr, &check.actual_balance)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "sync_cw20_balance")
+        .add_attribute("cw20_address", check.address)
+        .add_attribute("ledger_balance", check.ledger_balance)
+        .add_attribute("actual_balance", check.actual_balance)
+        .add_attribute("surplus", check.surplus)
+        .add_attribute("deficit", check.deficit))
+}
+
+// receive a CW721 NFT
+// and record that we hold it on behalf of whoever sent it
+pub fn execute_receive_nft(
+    deps: DepsMut,
+    info: MessageInfo,
+    wrapped: Cw721ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the CW721 contract sending the NFT
+    let collection = info.sender;
+    // wrapped.sender is the address that sent it
+    let depositor = deps.api.addr_validate(&wrapped.sender)?;
+
+    NFTS.save(
+        deps.storage,
+        (collection.clone(), wrapped.token_id.clone()),
+        &depositor,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "receive_nft")
+        .add_attribute("collection", collection)
+        .add_attribute("token_id", wrapped.token_id)
+        .add_attribute("depositor", depositor))
+}
+
+// this sets the withdraw delay for a batch of NFTs
+// note that it does not withdraw them immediately
+pub fn start_nft_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+    mut token_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // before continuing, only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let collection = deps.api.addr_validate(&collection)?;
+
+    token_ids.sort();
+    token_ids.dedup();
+    if token_ids.is_empty() {
+        return Err(ContractError::NoNftsRequested {});
+    }
+
+    // we can only hand back what we actually hold
+    for token_id in token_ids.iter() {
+        if !NFTS.has(deps.storage, (collection.clone(), token_id.clone())) {
+            return Err(ContractError::NftNotEscrowed {
+                token_id: token_id.clone(),
+            });
+        }
+    }
+
+    // starting a withdrawal also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let amount = Uint128::from(token_ids.len() as u128);
+    let rewards_ready_at = save_withdrawal(
+        deps,
+        &env,
+        &config,
+        collection.to_string(),
+        amount,
+        false,
+        Some(token_ids),
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_nft_withdraw")
+        .add_attribute("collection", collection)
+        .add_attribute("withdrawal_ready_timestamp", rewards_ready_at.to_string()))
+}
+
+// this calls the CW721 collection passed in
+// and gets it to transfer the requested NFTs to the withdraw_address
+pub fn execute_nft_withdraw(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collection: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let withdrawal_ready = WITHDRAWAL_READY.load(deps.storage)?;
+    let withdraw_address = withdrawal_recipient(&config, &withdrawal_ready)?;
+
+    // before continuing, only the recipient can call this
+    // unless anyone is allowed to
+    let keeper_tip = ensure_can_claim(deps.storage, &config, &info.sender, &withdraw_address)?;
+
+    let collection = deps.api.addr_validate(&collection)?;
+
+    let wr = withdrawal_ready.ok_or(ContractError::WithdrawalNotRequested {})?;
+    if env.block.time <= wr.ready_at {
+        return Err(ContractError::WithdrawalNotReady {});
+    }
+
+    // check this is what we expect
+    let token_ids = match wr.nft_token_ids {
+        Some(token_ids) if collection == wr.denom_or_address => token_ids,
+        _ => return Err(ContractError::WithdrawalNftMismatch {}),
+    };
+
+    let msgs = release_nfts(deps.storage, &collection, token_ids, &withdraw_address)?;
+
+    // reset the timer now we've claimed the withdrawal
+    WITHDRAWAL_READY.save(deps.storage, &None)?;
+    let keeper_tip = pay_keeper_tip(deps.storage, &info.sender, keeper_tip)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_nft_withdraw")
+        .add_attribute("withdraw_address", withdraw_address)
+        .add_attribute("collection", collection)
+        .add_messages(msgs)
+        .add_messages(keeper_tip))
+}
+
+// takes NFTs out of escrow, and transfers them to the recipient
+fn release_nfts(
+    storage: &mut dyn Storage,
+    collection: &Addr,
+    token_ids: Vec<String>,
+    recipient: &Addr,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    for token_id in token_ids {
+        // they're no longer ours once this goes through
+        if NFTS
+            .may_load(storage, (collection.clone(), token_id.clone()))?
+            .is_none()
+        {
+            return Err(ContractError::NftNotEscrowed { token_id });
+        }
+        NFTS.remove(storage, (collection.clone(), token_id.clone()));
+
+        msgs.push(
+            WasmMsg::Execute {
+                contract_addr: collection.to_string(),
+                msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                    recipient: recipient.to_string(),
+                    token_id,
+                })?,
+                funds: vec![],
+            }
+            .into(),
+        );
+    }
+    Ok(msgs)
+}
+
+// staking never moves funds out of the contract
+// it is bonded to, and unbonds back to, this contract
+// and we never change the rewards withdraw address
+// so rewards are paid back into escrow too
+
+// checks the sender is the withdraw_address, and that the validator exists
+// returns the staking denom, which has to be one we support
+fn ensure_can_stake(
+    deps: Deps,
+    info: &MessageInfo,
+    validators: &[&str],
+) -> Result<String, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    for validator in validators {
+        if deps.querier.query_validator(*validator)?.is_none() {
+            return Err(ContractError::ValidatorNotFound {
+                validator: validator.to_string(),
+            });
+        }
+    }
+
+    let denom = deps.querier.query_bonded_denom()?;
+    if !NATIVE_DENOMS.has(deps.storage, denom.clone()) {
+        return Err(ContractError::UnsupportedDenom { denom });
+    }
+
+    Ok(denom)
+}
+
+pub fn delegate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let denom = ensure_can_stake(deps.as_ref(), &info, &[&validator])?;
+
+    // the keeper pool has to stay liquid to pay out tips
+    let delegatable = query_escrowed_balance(deps.as_ref(), &env.contract.address, &denom)?;
+    if amount > delegatable {
+        return Err(ContractError::InsufficientContractBalance {});
+    }
+
+    let msg = StakingMsg::Delegate {
+        validator: validator.clone(),
+        amount: Coin { denom, amount },
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "delegate")
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
+pub fn undelegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let denom = ensure_can_stake(deps.as_ref(), &info, &[&validator])?;
+
+    let msg = StakingMsg::Undelegate {
+        validator: validator.clone(),
+        amount: Coin { denom, amount },
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "undelegate")
+        .add_attribute("validator", validator)
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
+pub fn redelegate(
+    deps: DepsMut,
+    info: MessageInfo,
+    src_validator: String,
+    dst_validator: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let denom = ensure_can_stake(deps.as_ref(), &info, &[&src_validator, &dst_validator])?;
+
+    let msg = StakingMsg::Redelegate {
+        src_validator: src_validator.clone(),
+        dst_validator: dst_validator.clone(),
+        amount: Coin { denom, amount },
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "redelegate")
+        .add_attribute("src_validator", src_validator)
+        .add_attribute("dst_validator", dst_validator)
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
+pub fn claim_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ensure_can_stake(deps.as_ref(), &info, &[])?;
+
+    let msgs: Vec<CosmosMsg> = deps
+        .querier
+        .query_all_delegations(&env.contract.address)?
+        .into_iter()
+        .map(|delegation| {
+            DistributionMsg::WithdrawDelegatorReward {
+                validator: delegation.validator,
+            }
+            .into()
+        })
+        .collect();
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_rewards")
+        .add_attribute("validators", msgs.len().to_string())
+        .add_messages(msgs))
+}
+
+pub fn validate_execution_allow_list(
+    api: &dyn Api,
+    allow_list: &ExecutionAllowList,
+) -> Result<(), ContractError> {
+    for contract in allow_list.contracts.iter() {
+        api.addr_validate(contract)?;
+    }
+    Ok(())
+}
+
+// the delay a proposal has to wait out
+// the same as a withdrawal, unless it moves escrowed assets
+// in which case it could be spending from any vault, so it waits as long as the coldest one
+// and needs the reserve delay while there's a reserve floor
+fn proposal_delay(deps: Deps, config: &Config, msgs: &[CosmosMsg]) -> Result<u64, ContractError> {
+    let mut delay_in_days = config.withdraw_delay_in_days;
+    if !moves_escrowed_assets(deps, msgs) {
+        return Ok(delay_in_days);
+    }
+
+    for item in VAULTS.range(deps.storage, None, None, Order::Ascending) {
+        let (_, vault) = item?;
+        delay_in_days = delay_in_days.max(vault.delay_in_days);
+    }
+    if !config.reserve_floors.is_empty() {
+        let reserve_delay_in_days = config
+            .reserve_delay_in_days
+            .ok_or(ContractError::ReserveDelayNotConfigured {})?;
+        delay_in_days = delay_in_days.max(reserve_delay_in_days);
+    }
+    Ok(delay_in_days)
+}
+
+// does anything here send funds, or call a CW20 or a collection we hold NFTs from?
+fn moves_escrowed_assets(deps: Deps, msgs: &[CosmosMsg]) -> bool {
+    msgs.iter().any(|msg| match msg {
+        CosmosMsg::Bank(_) => true,
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr,
+            funds,
+            ..
+        }) => {
+            !funds.is_empty()
+                || holds_nfts_from(deps.storage, contract_addr)
+                || is_cw20(deps, contract_addr)
+        }
+        CosmosMsg::Wasm(WasmMsg::Instantiate { funds, .. }) => !funds.is_empty(),
+        _ => false,
+    })
+}
+
+fn holds_nfts_from(storage: &dyn Storage, collection: &str) -> bool {
+    NFTS.prefix(Addr::unchecked(collection))
+        .keys(storage, None, None, Order::Ascending)
+        .next()
+        .is_some()
+}
+
+// what a set of messages takes out of escrow
+#[derive(Default)]
+struct Outflows {
+    // the total of each native denom
+    native: Vec<(String, Uint128)>,
+    // the total of each CW20
+    cw20: Vec<(Addr, Uint128)>,
+    // the escrowed NFTs, by collection and token id
+    nfts: Vec<(Addr, String)>,
+}
+
+fn add_outflow<T: PartialEq>(
+    totals: &mut Vec<(T, Uint128)>,
+    asset: T,
+    amount: Uint128,
+) -> StdResult<()> {
+    match totals
+        .iter_mut()
+        .find(|(total_asset, _)| *total_asset == asset)
+    {
+        Some((_, total)) => *total = total.checked_add(amount)?,
+        None => totals.push((asset, amount)),
+    }
+    Ok(())
+}
+
+fn escrow_outflows(deps: Deps, msgs: &[CosmosMsg]) -> StdResult<Outflows> {
+    let mut outflows = Outflows::default();
+    for msg in msgs {
+        match msg {
+            CosmosMsg::Bank(BankMsg::Send { amount, .. })
+            | CosmosMsg::Bank(BankMsg::Burn { amount })
+            | CosmosMsg::Wasm(WasmMsg::Instantiate { funds: amount, .. }) => {
+                for coin in amount {
+                    add_outflow(&mut outflows.native, coin.denom.clone(), coin.amount)?;
+                }
+            }
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr,
+                msg,
+                funds,
+            }) => {
+                for coin in funds {
+                    add_outflow(&mut outflows.native, coin.denom.clone(), coin.amount)?;
+                }
+                if holds_nfts_from(deps.storage, contract_addr) {
+                    let collection = Addr::unchecked(contract_addr);
+                    if let Ok(
+                        Cw721ExecuteMsg::TransferNft { token_id, .. }
+                        | Cw721ExecuteMsg::SendNft { token_id, .. }
+                        | Cw721ExecuteMsg::Burn { token_id },
+                    ) = from_binary(msg)
+                    {
+                        if NFTS.has(deps.storage, (collection.clone(), token_id.clone())) {
+                            outflows.nfts.push((collection, token_id));
+                        }
+                    }
+                } else if let Ok(
+                    Cw20ExecuteMsg::Transfer { amount, .. }
+                    | Cw20ExecuteMsg::Send { amount, .. }
+                    | Cw20ExecuteMsg::Burn { amount },
+                ) = from_binary(msg)
+                {
+                    if is_cw20(deps, contract_addr) {
+                        let cw20_addr = deps.api.addr_validate(contract_addr)?;
+                        add_outflow(&mut outflows.cw20, cw20_addr, amount)?;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(outflows)
+}
+
+// checks every message is on the allow-list, if there is one
+fn ensure_execution_allowed(config: &Config, msgs: &[CosmosMsg]) -> Result<(), ContractError> {
+    let allow_list = match &config.execution_allow_list {
+        Some(allow_list) => allow_list,
+        None => return Ok(()),
+    };
+
+    for msg in msgs {
+        let (msg_type, target) = match msg {
+            CosmosMsg::Bank(_) => (ExecutionMsgType::Bank, None),
+            CosmosMsg::Staking(_) => (ExecutionMsgType::Staking, None),
+            CosmosMsg::Distribution(_) => (ExecutionMsgType::Distribution, None),
+            CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, .. })
+            | CosmosMsg::Wasm(WasmMsg::Migrate { contract_addr, .. })
+            | CosmosMsg::Wasm(WasmMsg::UpdateAdmin { contract_addr, .. })
+            | CosmosMsg::Wasm(WasmMsg::ClearAdmin { contract_addr }) => {
+                (ExecutionMsgType::Wasm, Some(contract_addr))
+            }
+            // anything else, like instantiating a contract, is never allowed
+            _ => return Err(ContractError::ExecutionNotAllowed {}),
+        };
+
+        if !allow_list.msg_types.contains(&msg_type) {
+            return Err(ContractError::ExecutionNotAllowed {});
+        }
+        if let Some(target) = target {
+            if !allow_list.contracts.contains(target) {
+                return Err(ContractError::ExecutionNotAllowed {});
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// proposes a set of messages, which can be executed
+// once the withdraw delay has passed
+pub fn propose_execution(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msgs: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    if msgs.is_empty() {
+        return Err(ContractError::EmptyProposal {});
+    }
+    ensure_execution_allowed(&config, &msgs)?;
+
+    // proposing also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let delay_in_days = proposal_delay(deps.as_ref(), &config, &msgs)?;
+    let ready_at = days_after(env.block.time, delay_in_days)?;
+
+    let id = PROPOSAL_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    PROPOSAL_COUNT.save(deps.storage, &id)?;
+
+    PROPOSALS.save(
+        deps.storage,
+        id,
+        &Proposal {
+            id,
+            msgs,
+            delay_in_days,
+            ready_at,
+            status: ProposalStatus::Pending,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "propose_execution")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("proposal_ready_timestamp", ready_at.to_string()))
+}
+
+// executes a proposal, if the delay has passed
+pub fn execute_proposal(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let proposal = PROPOSALS.load(deps.storage, id)?;
+    if proposal.status != ProposalStatus::Pending {
+        return Err(ContractError::ProposalNotPending {});
+    }
+    if env.block.time <= proposal.ready_at {
+        return Err(ContractError::ProposalNotReady {});
+    }
+
+    // the allow-list could have been tightened in the meantime
+    ensure_execution_allowed(&config, &proposal.msgs)?;
+
+    // and a reserve floor or colder vault could have been set up
+    if proposal_delay(deps.as_ref(), &config, &proposal.msgs)? > proposal.delay_in_days {
+        return Err(ContractError::ProposalDelayTooShort {});
+    }
+
+    // a proposal spends from the default vault
+    // so it can't touch named vaults, or anything set aside for agreements and streams
+    let outflows = escrow_outflows(deps.as_ref(), &proposal.msgs)?;
+    let native = outflows
+        .native
+        .iter()
+        .map(|(denom, amount)| (denom.as_str(), *amount));
+    let cw20 = outflows
+        .cw20
+        .iter()
+        .map(|(cw20_addr, amount)| (cw20_addr.as_str(), *amount));
+    for (denom_or_address, amount) in native.chain(cw20) {
+        let available =
+            query_vault_balance(deps.as_ref(), &env.contract.address, None, denom_or_address)?;
+        if amount > available {
+            return Err(ContractError::InsufficientVaultBalance {});
+        }
+    }
+
+    // and the ledgers have to follow what leaves
+    for (cw20_addr, amount) in outflows.cw20 {
+        debit_cw20(deps.storage, &deps.querier, &env, &cw20_addr, amount)?;
+    }
+    for (collection, token_id) in outflows.nfts {
+        NFTS.remove(deps.storage, (collection, token_id));
+    }
+
+    PROPOSALS.save(
+        deps.storage,
+        id,
+        &Proposal {
+            status: ProposalStatus::Executed,
+            ..proposal.clone()
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_proposal")
+        .add_attribute("proposal_id", id.to_string())
+        .add_messages(proposal.msgs))
+}
+
+// either role can throw out a pending proposal
+pub fn cancel_proposal(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let proposal = PROPOSALS.load(deps.storage, id)?;
+    if proposal.status != ProposalStatus::Pending {
+        return Err(ContractError::ProposalNotPending {});
+    }
+
+    PROPOSALS.save(
+        deps.storage,
+        id,
+        &Proposal {
+            status: ProposalStatus::Cancelled,
+            ..proposal
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_proposal")
+        .add_attribute("proposal_id", id.to_string())
+        .add_attribute("cancelled_by", info.sender))
+}
+
+// sets aside native funds to tip keepers with
+pub fn fund_keeper_pool(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    if info.funds.is_empty() {
+        return Err(ContractError::NoFundsSent {});
+    }
+
+    for coin in info.funds.iter() {
+        KEEPER_POOL.update(deps.storage, coin.denom.clone(), |pool| -> StdResult<_> {
+            Ok(pool.unwrap_or_default().checked_add(coin.amount)?)
+        })?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "fund_keeper_pool")
+        .add_attribute("funded_by", info.sender)
+        .add_attribute(
+            "amount",
+            info.funds
+                .iter()
+                .map(|coin| coin.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        ))
+}
+
+// moves escrowed assets to the safe_harbor_address, with no delay
+// the override_address still can't send funds anywhere else
+pub fn evacuate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    assets: Vec<Asset>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    let safe_harbor_address = config
+        .safe_harbor_address
+        .clone()
+        .ok_or(ContractError::SafeHarborNotConfigured {})?;
+
+    if assets.is_empty() {
+        return Err(ContractError::NoAssetsToEvacuate {});
+    }
+
+    let mut msgs: Vec<CosmosMsg> = vec![];
+    let mut attributes: Vec<Attribute> = vec![];
+    // the same asset could be listed twice
+    let mut evacuated: Vec<(String, Uint128)> = vec![];
+    let already_evacuated = |evacuated: &Vec<(String, Uint128)>, denom: &str| -> Uint128 {
+        evacuated
+            .iter()
+            .filter(|(evacuated_denom, _)| evacuated_denom == denom)
+            .map(|(_, amount)| *amount)
+            .sum()
+    };
+    for asset in assets.iter() {
+        match asset {
+            Asset::Native { denom, amount } => {
+                // the keeper pool isn't ours to move
+                let keeper_pool = KEEPER_POOL
+                    .may_load(deps.storage, denom.clone())?
+                    .unwrap_or_default();
+                let liquid = deps
+                    .querier
+                    .query_balance(&env.contract.address, denom)?
+                    .amount
+                    .saturating_sub(keeper_pool);
+                let already_sent = already_evacuated(&evacuated, denom);
+                if liquid < already_sent.checked_add(*amount).map_err(StdError::from)? {
+                    return Err(ContractError::InsufficientContractBalance {});
+                }
+                debit_evacuated(deps.branch(), &env, &config, denom, already_sent, *amount)?;
+                evacuated.push((denom.clone(), *amount));
+
+                msgs.push(
+                    BankMsg::Send {
+                        to_address: safe_harbor_address.to_string(),
+                        amount: vec![Coin {
+                            denom: denom.clone(),
+                            amount: *amount,
+                        }],
+                    }
+                    .into(),
+                );
+                attributes.push(Attribute::new("native", format!("{}{}", amount, denom)));
+            }
+            Asset::Cw20 { address, amount } => {
+                let cw20_addr = deps.api.addr_validate(address)?;
+                let already_sent = already_evacuated(&evacuated, cw20_addr.as_str());
+                debit_evacuated(
+                    deps.branch(),
+                    &env,
+                    &config,
+                    cw20_addr.as_str(),
+                    already_sent,
+                    *amount,
+                )?;
+                debit_cw20(deps.storage, &deps.querier, &env, &cw20_addr, *amount)?;
+                evacuated.push((cw20_addr.to_string(), *amount));
+
+                msgs.push(
+                    WasmMsg::Execute {
+                        contract_addr: cw20_addr.to_string(),
+                        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                            recipient: safe_harbor_address.to_string(),
+                            amount: *amount,
+                        })?,
+                        funds: vec![],
+                    }
+                    .into(),
+                );
+                attributes.push(Attribute::new("cw20", format!("{}:{}", cw20_addr, amount)));
+            }
+            Asset::Nft {
+                collection,
+                token_ids,
+            } => {
+                if token_ids.is_empty() {
+                    return Err(ContractError::NoNftsRequested {});
+                }
+                let collection = deps.api.addr_validate(collection)?;
+                msgs.extend(release_nfts(
+                    deps.storage,
+                    &collection,
+                    token_ids.clone(),
+                    &safe_harbor_address,
+                )?);
+                for token_id in token_ids {
+                    attributes.push(Attribute::new(
+                        "nft",
+                        format!("{}/{}", collection, token_id),
+                    ));
+                }
+            }
+        }
+    }
+
+    let id = EVACUATION_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    EVACUATION_COUNT.save(deps.storage, &id)?;
+    EVACUATIONS.save(
+        deps.storage,
+        id,
+        &Evacuation {
+            id,
+            evacuated_by: info.sender.clone(),
+            evacuated_at: env.block.time,
+            safe_harbor_address: safe_harbor_address.clone(),
+            assets,
+        },
+    )?;
+
+    // whatever was pending could be counting on what just left
+    let withdrawal_cancelled = WITHDRAWAL_READY.may_load(deps.storage)?.flatten().is_some();
+    WITHDRAWAL_READY.save(deps.storage, &None)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "evacuate")
+        .add_attribute("evacuation_id", id.to_string())
+        .add_attribute("evacuated_by", info.sender)
+        .add_attribute("safe_harbor_address", safe_harbor_address)
+        .add_attribute("withdrawal_cancelled", withdrawal_cancelled.to_string())
+        .add_attributes(attributes)
+        .add_messages(msgs))
+}
+
+// moves an asset between vaults
+// into a vault that's at least as cold, this is instant
+// otherwise it waits out the delay of the vault it leaves
+pub fn start_vault_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    from: Option<String>,
+    to: Option<String>,
+    denom_or_address: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    if from == to {
+        return Err(ContractError::SameVault {});
+    }
+    for name in from.iter().chain(to.iter()) {
+        validate_vault_name(name)?;
+    }
+    // funds can only go into a vault that's been set up
+    if let Some(name) = &to {
+        ensure_vault_exists(deps.storage, name)?;
+    }
+
+    // it all has to be there, even for the default vault
+    let available = query_vault_balance(
+        deps.as_ref(),
+        &env.contract.address,
+        from.as_deref(),
+        &denom_or_address,
+    )?;
+    if amount > available {
+        return Err(ContractError::InsufficientVaultBalance {});
+    }
+
+    let from_delay_in_days = vault_delay(deps.storage, &config, &from)?;
+    let to_delay_in_days = vault_delay(deps.storage, &config, &to)?;
+
+    if to_delay_in_days >= from_delay_in_days {
+        debit_vault(deps.storage, &from, &denom_or_address, amount)?;
+        credit_vault(deps.storage, &to, &denom_or_address, amount)?;
+
+        return Ok(Response::new()
+            .add_attribute("action", "vault_transfer")
+            .add_attribute("from", from.as_deref().unwrap_or(DEFAULT_VAULT))
+            .add_attribute("to", to.as_deref().unwrap_or(DEFAULT_VAULT))
+            .add_attribute("denom_or_address", denom_or_address)
+            .add_attribute("amount", amount));
+    }
+
+    let ready_at = days_after(env.block.time, from_delay_in_days)?;
+
+    let id = VAULT_TRANSFER_COUNT
+        .may_load(deps.storage)?
+        .unwrap_or_default()
+        + 1;
+    VAULT_TRANSFER_COUNT.save(deps.storage, &id)?;
+    VAULT_TRANSFERS.save(
+        deps.storage,
+        id,
+        &VaultTransfer {
+            id,
+            from,
+            to,
+            denom_or_address,
+            amount,
+            ready_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "start_vault_transfer")
+        .add_attribute("vault_transfer_id", id.to_string())
+        .add_attribute("vault_transfer_ready_timestamp", ready_at.to_string()))
+}
+
+// completes a move between vaults, once its delay has passed
+pub fn execute_vault_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let transfer = VAULT_TRANSFERS.load(deps.storage, id)?;
+    if env.block.time <= transfer.ready_at {
+        return Err(ContractError::VaultTransferNotReady {});
+    }
+    VAULT_TRANSFERS.remove(deps.storage, id);
+
+    // the funds could have been withdrawn or evacuated in the meantime
+    let available = query_vault_balance(
+        deps.as_ref(),
+        &env.contract.address,
+        transfer.from.as_deref(),
+        &transfer.denom_or_address,
+    )?;
+    if transfer.amount > available {
+        return Err(ContractError::InsufficientVaultBalance {});
+    }
+
+    debit_vault(
+        deps.storage,
+        &transfer.from,
+        &transfer.denom_or_address,
+        transfer.amount,
+    )?;
+    credit_vault(
+        deps.storage,
+        &transfer.to,
+        &transfer.denom_or_address,
+        transfer.amount,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "execute_vault_transfer")
+        .add_attribute("vault_transfer_id", id.to_string())
+        .add_attribute("from", transfer.from.as_deref().unwrap_or(DEFAULT_VAULT))
+        .add_attribute("to", transfer.to.as_deref().unwrap_or(DEFAULT_VAULT))
+        .add_attribute("denom_or_address", transfer.denom_or_address)
+        .add_attribute("amount", transfer.amount))
+}
+
+// either role can throw out a pending move between vaults
+pub fn cancel_vault_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // explicitly crash out if it doesn't exist
+    VAULT_TRANSFERS.load(deps.storage, id)?;
+    VAULT_TRANSFERS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_vault_transfer")
+        .add_attribute("vault_transfer_id", id.to_string())
+        .add_attribute("cancelled_by", info.sender))
+}
+
+// pays an asset out of escrow, outside of the withdrawal flow
+// it comes out of the default vault, and respects the reserve floor
+fn pay_out(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    ensure_above_reserve_floor(deps.as_ref(), env, config, denom_or_address, amount)?;
+    ensure_vault_balance(deps.as_ref(), env, &None, denom_or_address, amount)?;
+    send_out(deps, env, recipient, denom_or_address, amount)
+}
+
+// sends an asset out of escrow, with no checks on where it comes from
+// for funds that were already set aside for the recipient
+fn send_out(
+    deps: DepsMut,
+    env: &Env,
+    recipient: &Addr,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    if NATIVE_DENOMS.has(deps.storage, denom_or_address.to_string()) {
+        let keeper_pool = KEEPER_POOL
+            .may_load(deps.storage, denom_or_address.to_string())?
+            .unwrap_or_default();
+        let liquid = deps
+            .querier
+            .query_balance(&env.contract.address, denom_or_address)?
+            .amount
+            .saturating_sub(keeper_pool);
+        if liquid < amount {
+            return Err(ContractError::InsufficientContractBalance {});
+        }
+
+        return Ok(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![Coin {
+                denom: denom_or_address.to_string(),
+                amount,
+            }],
+        }
+        .into());
+    }
+
+    let cw20_addr = deps.api.addr_validate(denom_or_address)?;
+    debit_cw20(deps.storage, &deps.querier, env, &cw20_addr, amount)?;
+
+    Ok(WasmMsg::Execute {
+        contract_addr: cw20_addr.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }
+    .into())
+}
+
+// sets up a recurring payout
+// the first instalment is due once the withdraw delay has passed
+// which gives the override_address time to cancel it
+#[allow(clippy::too_many_arguments)]
+pub fn create_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    denom_or_address: String,
+    amount: Uint128,
+    interval_in_days: u64,
+    count: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    if amount.is_zero()
+        || interval_in_days == 0
+        || interval_in_days > MAX_SCHEDULE_INTERVAL_IN_DAYS
+        || count == 0
+    {
+        return Err(ContractError::InvalidSchedule {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    ensure_supported_asset(deps.as_ref(), &denom_or_address)?;
+
+    // creating a schedule also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let next_payment_at = days_after(env.block.time, config.withdraw_delay_in_days)?;
+
+    let id = SCHEDULE_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    SCHEDULE_COUNT.save(deps.storage, &id)?;
+    SCHEDULES.save(
+        deps.storage,
+        id,
+        &Schedule {
+            id,
+            recipient: recipient.clone(),
+            denom_or_address: denom_or_address.clone(),
+            amount,
+            interval_in_days,
+            count,
+            paid: 0,
+            next_payment_at,
+            paused: false,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_schedule")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute("recipient", recipient)
+        .add_attribute("denom_or_address", denom_or_address)
+        .add_attribute("amount", amount)
+        .add_attribute("count", count.to_string())
+        .add_attribute("next_payment_timestamp", next_payment_at.to_string()))
+}
+
+// pays out every instalment of a schedule that's due
+// anyone can trigger this, the funds only go to the recipient
+pub fn claim_scheduled_payment(
+    mut deps: DepsMut,
+    env: Env,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut schedule = SCHEDULES.load(deps.storage, id)?;
+
+    if schedule.paused {
+        return Err(ContractError::SchedulePaused {});
+    }
+    if env.block.time < schedule.next_payment_at {
+        return Err(ContractError::ScheduleNotDue {});
+    }
+
+    // catch up on anything missed since the last claim
+    let interval_in_seconds = schedule
+        .interval_in_days
+        .checked_mul(86400)
+        .ok_or(ContractError::InvalidSchedule {})?;
+    let elapsed = env.block.time.seconds() - schedule.next_payment_at.seconds();
+    let due = (1 + elapsed / interval_in_seconds).min(schedule.count - schedule.paid);
+    let amount = schedule
+        .amount
+        .checked_mul(Uint128::from(due))
+        .map_err(StdError::from)?;
+
+    let msg = pay_out(
+        deps.branch(),
+        &env,
+        &config,
+        &schedule.recipient,
+        &schedule.denom_or_address,
+        amount,
+    )?;
+
+    schedule.paid += due;
+    schedule.next_payment_at = schedule.next_payment_at.plus_seconds(
+        due.checked_mul(interval_in_seconds)
+            .ok_or(ContractError::InvalidSchedule {})?,
+    );
+    if schedule.paid == schedule.count {
+        SCHEDULES.remove(deps.storage, id);
+    } else {
+        SCHEDULES.save(deps.storage, id, &schedule)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_scheduled_payment")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute("recipient", schedule.recipient.clone())
+        .add_attribute("instalments", due.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute(
+            "remaining_instalments",
+            (schedule.count - schedule.paid).to_string(),
+        )
+        .add_message(msg))
+}
+
+// either role can pause a schedule
+pub fn pause_schedule(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut schedule = SCHEDULES.load(deps.storage, id)?;
+    if schedule.paused {
+        return Err(ContractError::SchedulePaused {});
+    }
+    schedule.paused = true;
+    SCHEDULES.save(deps.storage, id, &schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "pause_schedule")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute("paused_by", info.sender))
+}
+
+// picks a paused schedule back up
+// nothing is due until the withdraw delay has passed again
+// so the override_address gets the same chance to step in as on creation
+pub fn resume_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let mut schedule = SCHEDULES.load(deps.storage, id)?;
+    if !schedule.paused {
+        return Err(ContractError::ScheduleNotPaused {});
+    }
+
+    // anything missed while paused is pushed back, not lost
+    let earliest = days_after(env.block.time, config.withdraw_delay_in_days)?;
+    schedule.next_payment_at = schedule.next_payment_at.max(earliest);
+    schedule.paused = false;
+    SCHEDULES.save(deps.storage, id, &schedule)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "resume_schedule")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute(
+            "next_payment_timestamp",
+            schedule.next_payment_at.to_string(),
+        ))
+}
+
+// either role can cancel a schedule
+// anything already paid out stays paid
+pub fn cancel_schedule(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // explicitly crash out if it doesn't exist
+    SCHEDULES.load(deps.storage, id)?;
+    SCHEDULES.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_schedule")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute("cancelled_by", info.sender))
+}
+
+// sets up a stream, which accrues to the recipient every second
+// it can't start until the withdraw delay has passed
+// which gives the override_address time to cancel it
+#[allow(clippy::too_many_arguments)]
+pub fn create_stream(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    denom_or_address: String,
+    total: Uint128,
+    start: Timestamp,
+    end: Timestamp,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    if total.is_zero() || end <= start {
+        return Err(ContractError::InvalidStream {});
+    }
+    let earliest = days_after(env.block.time, config.withdraw_delay_in_days)?;
+    if start < earliest {
+        return Err(ContractError::StreamStartsTooSoon { earliest });
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    ensure_supported_asset(deps.as_ref(), &denom_or_address)?;
+
+    // it all has to be in the default vault, and then it's set aside
+    // so the floor is checked once, here, rather than on every withdrawal
+    ensure_above_reserve_floor(deps.as_ref(), &env, &config, &denom_or_address, total)?;
+    ensure_vault_balance(deps.as_ref(), &env, &None, &denom_or_address, total)?;
+    commit(
+        deps.storage,
+        &COMMITTED_TO_STREAMS,
+        &denom_or_address,
+        total,
+    )?;
+
+    // creating a stream also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let id = STREAM_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    STREAM_COUNT.save(deps.storage, &id)?;
+    STREAMS.save(
+        deps.storage,
+        id,
+        &Stream {
+            id,
+            recipient: recipient.clone(),
+            denom_or_address: denom_or_address.clone(),
+            total,
+            start,
+            end,
+            withdrawn: Uint128::zero(),
+            cancelled_at: None,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_stream")
+        .add_attribute("stream_id", id.to_string())
+        .add_attribute("recipient", recipient)
+        .add_attribute("denom_or_address", denom_or_address)
+        .add_attribute("total", total)
+        .add_attribute("start", start.to_string())
+        .add_attribute("end", end.to_string()))
+}
+
+// pays the recipient everything that's accrued so far
+// out of what was set aside for the stream
+pub fn withdraw_from_stream(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let mut stream = STREAMS.load(deps.storage, id)?;
+
+    // only the recipient can call this
+    ensure_eq!(
+        info.sender,
+        stream.recipient,
+        ContractError::Unauthorized {}
+    );
+
+    let amount = stream.streamed(env.block.time) - stream.withdrawn;
+    if amount.is_zero() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+
+    stream.withdrawn += amount;
+    STREAMS.save(deps.storage, id, &stream)?;
+    release_commitment(
+        deps.storage,
+        &COMMITTED_TO_STREAMS,
+        &stream.denom_or_address,
+        amount,
+    )?;
+
+    let msg = send_out(
+        deps,
+        &env,
+        &stream.recipient,
+        &stream.denom_or_address,
+        amount,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "withdraw_from_stream")
+        .add_attribute("stream_id", id.to_string())
+        .add_attribute("recipient", stream.recipient)
+        .add_attribute("amount", amount)
+        .add_message(msg))
+}
+
+// stops a stream accruing
+// the unaccrued part never left escrow, so it just stops being set aside
+pub fn cancel_stream(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config
+        .override_address
+        .clone()
+        .ok_or(ContractError::OverrideRenounced {})?;
+
+    // only override_address can call this
+    ensure_eq!(
+        info.sender,
+        override_address,
+        ContractError::Unauthorized {}
+    );
+
+    let mut stream = STREAMS.load(deps.storage, id)?;
+    if stream.cancelled_at.is_some() {
+        return Err(ContractError::StreamCancelled {});
+    }
+    stream.cancelled_at = Some(env.block.time);
+    STREAMS.save(deps.storage, id, &stream)?;
+
+    let streamed = stream.streamed(env.block.time);
+    release_commitment(
+        deps.storage,
+        &COMMITTED_TO_STREAMS,
+        &stream.denom_or_address,
+        stream.total - streamed,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_stream")
+        .add_attribute("stream_id", id.to_string())
+        .add_attribute("streamed", streamed)
+        .add_attribute("returned", stream.total - streamed))
+}
+
+// sets up a grant paid against milestones, with the withdraw_address as payer
+// the total is set aside, so it can't be withdrawn while the milestones are unsettled
+// nothing can be paid until the withdraw delay has passed
+pub fn create_agreement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    payee: String,
+    arbiter: Option<String>,
+    denom_or_address: String,
+    milestones: Vec<MilestoneTerms>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let payee = deps.api.addr_validate(&payee)?;
+    let arbiter = arbiter
+        .map(|arbiter| deps.api.addr_validate(&arbiter))
+        .transpose()?;
+    // the arbiter can't be either party
+    if payee == config.withdraw_address
+        || arbiter.as_ref() == Some(&payee)
+        || arbiter.as_ref() == Some(&config.withdraw_address)
+    {
+        return Err(ContractError::InvalidAgreement {});
+    }
+    ensure_supported_asset(deps.as_ref(), &denom_or_address)?;
+
+    let active_at = days_after(env.block.time, config.withdraw_delay_in_days)?;
+    if milestones.is_empty()
+        || milestones
+            .iter()
+            .any(|terms| terms.amount.is_zero() || terms.deadline <= active_at)
+    {
+        return Err(ContractError::InvalidAgreement {});
+    }
+
+    // it all has to be in the default vault, above any reserve floor, and then it's set aside
+    // so the floor is checked once, here, rather than on every payout
+    let total = milestones
+        .iter()
+        .try_fold(Uint128::zero(), |total, terms| {
+            total.checked_add(terms.amount)
+        })
+        .map_err(StdError::from)?;
+    ensure_above_reserve_floor(deps.as_ref(), &env, &config, &denom_or_address, total)?;
+    ensure_vault_balance(deps.as_ref(), &env, &None, &denom_or_address, total)?;
+    commit(
+        deps.storage,
+        &COMMITTED_TO_AGREEMENTS,
+        &denom_or_address,
+        total,
+    )?;
+
+    // creating an agreement also counts as a sign of life
+    LAST_WITHDRAW_HEARTBEAT.save(deps.storage, &env.block.time)?;
+
+    let id = AGREEMENT_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    AGREEMENT_COUNT.save(deps.storage, &id)?;
+    AGREEMENTS.save(
+        deps.storage,
+        id,
+        &Agreement {
+            id,
+            payee: payee.clone(),
+            arbiter,
+            denom_or_address: denom_or_address.clone(),
+            milestones: milestones
+                .into_iter()
+                .map(|terms| Milestone {
+                    amount: terms.amount,
+                    deadline: terms.deadline,
+                    status: MilestoneStatus::Pending,
+                })
+                .collect(),
+            active_at,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_agreement")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("payee", payee)
+        .add_attribute("denom_or_address", denom_or_address)
+        .add_attribute("total", total)
+        .add_attribute("agreement_active_timestamp", active_at.to_string()))
+}
+
+// either role can throw out an agreement that isn't active yet
+// after that, it can only be settled milestone by milestone
+pub fn cancel_agreement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+
+    // only withdraw_address or override_address can call this
+    if info.sender != config.withdraw_address && Some(&info.sender) != override_address.as_ref() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let agreement = AGREEMENTS.load(deps.storage, id)?;
+    if env.block.time > agreement.active_at {
+        return Err(ContractError::AgreementActive {});
+    }
+
+    let total = agreement
+        .milestones
+        .iter()
+        .map(|milestone| milestone.amount)
+        .sum();
+    release_commitment(
+        deps.storage,
+        &COMMITTED_TO_AGREEMENTS,
+        &agreement.denom_or_address,
+        total,
+    )?;
+    AGREEMENTS.remove(deps.storage, id);
+
+    Ok(Response::new()
+        .add_attribute("action", "cancel_agreement")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("cancelled_by", info.sender))
+}
+
+// the payer signs off on a milestone, paying the payee
+// this also settles a dispute in the payee's favour
+pub fn approve_milestone(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    milestone: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // only withdraw_address can call this
+    ensure_eq!(
+        info.sender,
+        config.withdraw_address,
+        ContractError::Unauthorized {}
+    );
+
+    let mut agreement = load_active_agreement(deps.storage, &env, id)?;
+    let entry = find_milestone(&mut agreement, milestone)?;
+    if !matches!(
+        entry.status,
+        MilestoneStatus::Pending | MilestoneStatus::Disputed
+    ) {
+        return Err(ContractError::MilestoneSettled {});
+    }
+
+    let msg = settle_milestone(deps, &env, agreement, milestone, true)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "approve_milestone")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("milestone", milestone.to_string())
+        .add_messages(msg))
+}
+
+// either party can dispute a milestone before its deadline
+// which stops it being refunded until the arbiter settles it
+pub fn dispute_milestone(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    milestone: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut agreement = load_active_agreement(deps.storage, &env, id)?;
+
+    // only the payer or the payee can call this
+    if info.sender != config.withdraw_address && info.sender != agreement.payee {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let entry = find_milestone(&mut agreement, milestone)?;
+    if entry.status != MilestoneStatus::Pending {
+        return Err(ContractError::MilestoneSettled {});
+    }
+    if env.block.time > entry.deadline {
+        return Err(ContractError::DeadlinePassed {});
+    }
+    entry.status = MilestoneStatus::Disputed;
+    AGREEMENTS.save(deps.storage, id, &agreement)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "dispute_milestone")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("milestone", milestone.to_string())
+        .add_attribute("disputed_by", info.sender))
+}
+
+// settles a disputed milestone, either paying the payee or refunding it
+// this can be done by the arbiter, or the override_address as a last resort
+pub fn resolve_dispute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+    milestone: u64,
+    release: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+    let mut agreement = load_active_agreement(deps.storage, &env, id)?;
+
+    // only the arbiter or override_address can call this
+    if Some(&info.sender) != agreement.arbiter.as_ref()
+        && Some(&info.sender) != override_address.as_ref()
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let entry = find_milestone(&mut agreement, milestone)?;
+    if entry.status != MilestoneStatus::Disputed {
+        return Err(ContractError::MilestoneNotDisputed {});
+    }
+
+    let msg = settle_milestone(deps, &env, agreement, milestone, release)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "resolve_dispute")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("milestone", milestone.to_string())
+        .add_attribute("release", release.to_string())
+        .add_attribute("resolved_by", info.sender)
+        .add_messages(msg))
+}
+
+// a milestone that wasn't approved or disputed by its deadline goes back to escrow
+// anyone can trigger this
+pub fn refund_milestone(
+    deps: DepsMut,
+    env: Env,
+    id: u64,
+    milestone: u64,
+) -> Result<Response, ContractError> {
+    let mut agreement = AGREEMENTS.load(deps.storage, id)?;
+
+    let entry = find_milestone(&mut agreement, milestone)?;
+    if entry.status != MilestoneStatus::Pending {
+        return Err(ContractError::MilestoneSettled {});
+    }
+    if env.block.time <= entry.deadline {
+        return Err(ContractError::DeadlineNotPassed {});
+    }
+
+    settle_milestone(deps, &env, agreement, milestone, false)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "refund_milestone")
+        .add_attribute("agreement_id", id.to_string())
+        .add_attribute("milestone", milestone.to_string()))
+}
+
+fn load_active_agreement(
+    storage: &dyn Storage,
+    env: &Env,
+    id: u64,
+) -> Result<Agreement, ContractError> {
+    let agreement = AGREEMENTS.load(storage, id)?;
+    if env.block.time <= agreement.active_at {
+        return Err(ContractError::AgreementNotActive {});
+    }
+    Ok(agreement)
+}
+
+fn find_milestone(
+    agreement: &mut Agreement,
+    milestone: u64,
+) -> Result<&mut Milestone, ContractError> {
+    agreement
+        .milestones
+        .get_mut(milestone as usize)
+        .ok_or(ContractError::MilestoneNotFound { milestone })
+}
+
+// marks a milestone paid or refunded, and stops setting its amount aside
+// if it's paid, this returns the message paying the payee
+// out of what was set aside, so the vault and floor checks don't apply again
+fn settle_milestone(
+    deps: DepsMut,
+    env: &Env,
+    mut agreement: Agreement,
+    milestone: u64,
+    release: bool,
+) -> Result<Option<CosmosMsg>, ContractError> {
+    let entry = find_milestone(&mut agreement, milestone)?;
+    let amount = entry.amount;
+    entry.status = if release {
+        MilestoneStatus::Paid
+    } else {
+        MilestoneStatus::Refunded
+    };
+    AGREEMENTS.save(deps.storage, agreement.id, &agreement)?;
+
+    // either way it's no longer set aside
+    // and if it was refunded, it's back in the default vault
+    release_commitment(
+        deps.storage,
+        &COMMITTED_TO_AGREEMENTS,
+        &agreement.denom_or_address,
+        amount,
+    )?;
+    if !release {
+        return Ok(None);
+    }
+
+    let msg = send_out(
+        deps,
+        env,
+        &agreement.payee,
+        &agreement.denom_or_address,
+        amount,
+    )?;
+    Ok(Some(msg))
+}
+
+// sets an amount of an asset aside
+fn commit(
+    storage: &mut dyn Storage,
+    commitments: &Map<String, Uint128>,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    commitments.update(
+        storage,
+        denom_or_address.to_string(),
+        |committed| -> StdResult<_> { Ok(committed.unwrap_or_default().checked_add(amount)?) },
+    )?;
+    Ok(())
+}
+
+// stops setting an amount of an asset aside
+fn release_commitment(
+    storage: &mut dyn Storage,
+    commitments: &Map<String, Uint128>,
+    denom_or_address: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    let committed = commitments
+        .may_load(storage, denom_or_address.to_string())?
+        .unwrap_or_default();
+    commitments.save(
+        storage,
+        denom_or_address.to_string(),
+        &committed.saturating_sub(amount),
+    )
+}
+
+// sends a CW20 deposit back to whoever made it
+// the depositor can do this within the refund period, the override_address at any time
+pub fn refund_deposit(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let override_address = config.override_address.clone();
+    let mut deposit = DEPOSITS.load(deps.storage, id)?;
+
+    // only the depositor or override_address can call this
+    if Some(&info.sender) != override_address.as_ref() {
+        ensure_eq!(
+            info.sender,
+            deposit.depositor,
+            ContractError::Unauthorized {}
+        );
+        // the roles that can withdraw have to wait out the delay like anything else
+        if info.sender == config.withdraw_address
+            || Some(&info.sender) == config.recovery_address.as_ref()
+        {
+            return Err(ContractError::SelfRefundNotAllowed {});
+        }
+        let period_ends = config
+            .deposit_refund_period_in_days
+            .map(|days| days_after(deposit.received_at, days))
+            .transpose()?;
+        if !matches!(period_ends, Some(ends) if env.block.time <= ends) {
+            return Err(ContractError::RefundPeriodPassed {});
+        }
+    }
+
+    if deposit.refunded {
+        return Err(ContractError::DepositAlreadyRefunded {});
+    }
+
+    // like any other payout, a refund has to leave the reserve floor alone
+    let cw20_addr = deposit.cw20_address.clone();
+    ensure_above_reserve_floor(
+        deps.as_ref(),
+        &env,
+        &config,
+        cw20_addr.as_str(),
+        deposit.amount,
+    )?;
+
+    // and can't take anything set aside, or that a pending withdrawal from the same vault
+    // is counting on
+    let earmarked = match WITHDRAWAL_READY.may_load(deps.storage)?.flatten() {
+        Some(wr) if wr.denom_or_address == cw20_addr.as_str() && wr.vault == deposit.vault => {
+            wr.amount
+        }
+        _ => Uint128::zero(),
+    };
+    let available = query_vault_balance(
+        deps.as_ref(),
+        &env.contract.address,
+        deposit.vault.as_deref(),
+        cw20_addr.as_str(),
+    )?;
+    if deposit.amount > available.saturating_sub(earmarked) {
+        return Err(ContractError::DepositEarmarked {});
+    }
+
+    debit_vault(
+        deps.storage,
+        &deposit.vault,
+        cw20_addr.as_str(),
+        deposit.amount,
+    )?;
+    debit_cw20(
+        deps.storage,
+        &deps.querier,
+        &env,
+        &cw20_addr,
+        deposit.amount,
+    )?;
+    CW20_DEPOSITS.update(
+        deps.storage,
+        (cw20_addr.clone(), deposit.depositor.clone()),
+        |deposited| -> StdResult<_> {
+            Ok(deposited.unwrap_or_default().saturating_sub(deposit.amount))
+        },
+    )?;
+
+    deposit.refunded = true;
+    DEPOSITS.save(deps.storage, id, &deposit)?;
+
+    let msg = WasmMsg::Execute {
+        contract_addr: cw20_addr.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: deposit.depositor.to_string(),
+            amount: deposit.amount,
+        })?,
+        funds: vec![],
+    };
+
+    Ok(Response::new()
+        .add_attribute("action", "refund_deposit")
+        .add_attribute("deposit_id", id.to_string())
+        .add_attribute("depositor", deposit.depositor)
+        .add_attribute("amount", deposit.amount)
+        .add_attribute("refunded_by", info.sender)
+        .add_message(msg))
 }
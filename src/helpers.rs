@@ -1,9 +1,24 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_binary, Addr, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{
+    to_binary, Addr, Api, CosmosMsg, Deps, Order, QuerierWrapper, StdResult, Storage, Timestamp,
+    Uint128, Uint64, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20QueryMsg};
+use sha2::{Digest, Sha256};
 
-use crate::msg::ExecuteMsg;
+use crate::error::ContractError;
+use crate::msg::{Cw20BalanceCheck, ExecuteMsg};
+use crate::state::{
+    GuardianKey, SignatureAlgorithm, BUDGET_SPEND, COMMITTED_TO_AGREEMENTS, COMMITTED_TO_STREAMS,
+    KEEPER_POOL, NATIVE_DENOMS, VAULT_BALANCES, VAULT_TRANSFERS, WITHDRAWAL_READY,
+};
+
+// the longest a withdrawal or vault can be made to wait, about ten years
+pub const MAX_DELAY_IN_DAYS: u64 = 3650;
+
+const NANOS_IN_DAY: u64 = 86_400_000_000_000;
 
 /// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
 /// for working with this.
@@ -25,3 +40,287 @@ impl CwTemplateContract {
         .into())
     }
 }
+
+/// Compares two dotted version strings, e.g. 0.2.0 and 0.3.0
+/// anything that isn't a number counts as 0
+pub fn is_older_version(version: &str, than: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        version
+            .split('.')
+            .map(|part| part.parse().unwrap_or_default())
+            .collect()
+    };
+    parse(version) < parse(than)
+}
+
+/// Asks a CW20 contract how much of it an address actually holds
+pub fn query_cw20_balance(
+    querier: &QuerierWrapper,
+    cw20_addr: &Addr,
+    address: &Addr,
+) -> StdResult<Uint128> {
+    let response: BalanceResponse = querier.query_wasm_smart(
+        cw20_addr,
+        &Cw20QueryMsg::Balance {
+            address: address.to_string(),
+        },
+    )?;
+    Ok(response.balance)
+}
+
+/// How much of a native denom or CW20 is held in escrow, and not staked
+/// the keeper pool isn't counted, as it isn't escrowed
+pub fn query_escrowed_balance(
+    deps: Deps,
+    contract_addr: &Addr,
+    denom_or_address: &str,
+) -> StdResult<Uint128> {
+    if NATIVE_DENOMS.has(deps.storage, denom_or_address.to_string()) {
+        let keeper_pool = KEEPER_POOL
+            .may_load(deps.storage, denom_or_address.to_string())?
+            .unwrap_or_default();
+        let balance = deps
+            .querier
+            .query_balance(contract_addr, denom_or_address)?;
+        return Ok(balance.amount.saturating_sub(keeper_pool));
+    }
+
+    let cw20_addr = deps.api.addr_validate(denom_or_address)?;
+    query_cw20_balance(&deps.querier, &cw20_addr, contract_addr)
+}
+
+/// How much of an asset is credited to any named vault
+pub fn allocated_to_vaults(storage: &dyn Storage, denom_or_address: &str) -> StdResult<Uint128> {
+    let mut allocated = Uint128::zero();
+    for item in VAULT_BALANCES.prefix(denom_or_address.to_string()).range(
+        storage,
+        None,
+        None,
+        Order::Ascending,
+    ) {
+        let (_, balance) = item?;
+        allocated = allocated.checked_add(balance)?;
+    }
+    Ok(allocated)
+}
+
+/// How much of an asset is waiting to move out of a vault
+pub fn pending_out_of_vault(
+    storage: &dyn Storage,
+    vault: Option<&str>,
+    denom_or_address: &str,
+) -> StdResult<Uint128> {
+    let mut pending = Uint128::zero();
+    for item in VAULT_TRANSFERS.range(storage, None, None, Order::Ascending) {
+        let (_, transfer) = item?;
+        if transfer.from.as_deref() == vault && transfer.denom_or_address == denom_or_address {
+            pending += transfer.amount;
+        }
+    }
+    Ok(pending)
+}
+
+/// How much of an asset is set aside for unsettled milestones
+pub fn committed_to_agreements(
+    storage: &dyn Storage,
+    denom_or_address: &str,
+) -> StdResult<Uint128> {
+    Ok(COMMITTED_TO_AGREEMENTS
+        .may_load(storage, denom_or_address.to_string())?
+        .unwrap_or_default())
+}
+
+/// How much of an asset is set aside for streams
+pub fn committed_to_streams(storage: &dyn Storage, denom_or_address: &str) -> StdResult<Uint128> {
+    Ok(COMMITTED_TO_STREAMS
+        .may_load(storage, denom_or_address.to_string())?
+        .unwrap_or_default())
+}
+
+/// How much of an asset is escrowed, less whatever is set aside for agreements and streams
+/// which is as good as spent
+pub fn query_uncommitted_balance(
+    deps: Deps,
+    contract_addr: &Addr,
+    denom_or_address: &str,
+) -> StdResult<Uint128> {
+    Ok(
+        query_escrowed_balance(deps, contract_addr, denom_or_address)?
+            .saturating_sub(committed_to_agreements(deps.storage, denom_or_address)?)
+            .saturating_sub(committed_to_streams(deps.storage, denom_or_address)?),
+    )
+}
+
+/// How much of an asset is in a vault, less anything waiting to move out
+/// the default vault, None, is whatever isn't credited to a named vault
+/// or set aside for an agreement or stream
+pub fn query_vault_balance(
+    deps: Deps,
+    contract_addr: &Addr,
+    vault: Option<&str>,
+    denom_or_address: &str,
+) -> StdResult<Uint128> {
+    let held = match vault {
+        Some(vault) => VAULT_BALANCES
+            .may_load(
+                deps.storage,
+                (denom_or_address.to_string(), vault.to_string()),
+            )?
+            .unwrap_or_default(),
+        None => query_uncommitted_balance(deps, contract_addr, denom_or_address)?
+            .saturating_sub(allocated_to_vaults(deps.storage, denom_or_address)?),
+    };
+    let pending = pending_out_of_vault(deps.storage, vault, denom_or_address)?;
+    Ok(held.saturating_sub(pending))
+}
+
+/// How much of an asset a budget category has spent in a period
+pub fn budget_spent(
+    storage: &dyn Storage,
+    category: &str,
+    denom_or_address: &str,
+    period: u64,
+) -> StdResult<Uint128> {
+    let spend = BUDGET_SPEND.may_load(
+        storage,
+        (category.to_string(), denom_or_address.to_string()),
+    )?;
+    Ok(spend
+        .filter(|spend| spend.period == period)
+        .map(|spend| spend.amount)
+        .unwrap_or_default())
+}
+
+/// How much of an asset a pending withdrawal will spend from a budget category in a period
+pub fn budget_pending(
+    storage: &dyn Storage,
+    category: &str,
+    denom_or_address: &str,
+    period: u64,
+) -> StdResult<Uint128> {
+    let pending = WITHDRAWAL_READY.may_load(storage)?.flatten();
+    Ok(pending
+        .filter(|wr| {
+            wr.category.as_deref() == Some(category)
+                && wr.denom_or_address == denom_or_address
+                && wr.budget_period == period
+        })
+        .map(|wr| wr.amount)
+        .unwrap_or_default())
+}
+
+/// Compares the ledger balance of a CW20 against what is actually held
+pub fn check_cw20_balance(
+    querier: &QuerierWrapper,
+    cw20_addr: &Addr,
+    contract_addr: &Addr,
+    ledger_balance: Uint128,
+) -> StdResult<Cw20BalanceCheck> {
+    let actual_balance = query_cw20_balance(querier, cw20_addr, contract_addr)?;
+
+    Ok(Cw20BalanceCheck {
+        address: cw20_addr.clone(),
+        ledger_balance,
+        actual_balance,
+        surplus: actual_balance.saturating_sub(ledger_balance),
+        deficit: ledger_balance.saturating_sub(actual_balance),
+    })
+}
+
+/// Checks a native denom is well formed
+/// this covers plain denoms like ujuno, as well as IBC denoms (ibc/{hash})
+/// and tokenfactory denoms (factory/{creator}/{subdenom})
+pub fn validate_native_denom(api: &dyn Api, denom: &str) -> Result<(), ContractError> {
+    let invalid = || ContractError::InvalidDenom {
+        denom: denom.to_string(),
+    };
+
+    if let Some(hash) = denom.strip_prefix("ibc/") {
+        // a sha256 hash, which chains display as uppercase hex
+        let is_hash = hash.len() == 64
+            && hash
+                .chars()
+                .all(|c| c.is_ascii_digit() || ('A'..='F').contains(&c));
+        return if is_hash { Ok(()) } else { Err(invalid()) };
+    }
+
+    if let Some(rest) = denom.strip_prefix("factory/") {
+        let (creator, subdenom) = rest.split_once('/').ok_or_else(invalid)?;
+        api.addr_validate(creator).map_err(|_| invalid())?;
+        let is_subdenom = !subdenom.is_empty()
+            && subdenom.len() <= 44
+            && subdenom
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+        return if is_subdenom { Ok(()) } else { Err(invalid()) };
+    }
+
+    // the same rules the cosmos sdk uses, [a-zA-Z][a-zA-Z0-9/:._-]{2,127}
+    let mut chars = denom.chars();
+    let is_denom = (3..=128).contains(&denom.len())
+        && matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || "/:._-".contains(c));
+    if is_denom {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// The digest the guardian_key signs to cancel a withdrawal
+/// the sha256 of override_withdraw:{chain_id}:{contract_address}:{withdrawal_id}:{nonce}
+pub fn guardian_cancellation_digest(
+    chain_id: &str,
+    contract_addr: &Addr,
+    withdrawal_id: u64,
+    nonce: u64,
+) -> [u8; 32] {
+    let payload = format!(
+        "override_withdraw:{}:{}:{}:{}",
+        chain_id, contract_addr, withdrawal_id, nonce
+    );
+    Sha256::digest(payload.as_bytes()).into()
+}
+
+/// Checks a guardian key is the right shape for its algorithm
+pub fn validate_guardian_key(key: &GuardianKey) -> Result<(), ContractError> {
+    let valid = match key.algorithm {
+        // compressed or uncompressed
+        SignatureAlgorithm::Secp256k1 => matches!(key.public_key.len(), 33 | 65),
+        SignatureAlgorithm::Ed25519 => key.public_key.len() == 32,
+    };
+    if !valid {
+        return Err(ContractError::InvalidGuardianKey {});
+    }
+    Ok(())
+}
+
+/// A delay can be at most MAX_DELAY_IN_DAYS
+pub fn validate_delay(days: u64) -> Result<(), ContractError> {
+    if days > MAX_DELAY_IN_DAYS {
+        return Err(ContractError::InvalidDelay {});
+    }
+    Ok(())
+}
+
+/// Both delays have to be in bounds,
+/// and the reserve delay, if set, has to be longer than the withdraw delay
+pub fn validate_delays(
+    withdraw_delay_in_days: u64,
+    reserve_delay_in_days: Option<u64>,
+) -> Result<(), ContractError> {
+    validate_delay(withdraw_delay_in_days)?;
+    match reserve_delay_in_days {
+        Some(days) if days <= withdraw_delay_in_days => Err(ContractError::InvalidReserveDelay {}),
+        Some(days) => validate_delay(days),
+        None => Ok(()),
+    }
+}
+
+/// The time a number of days after another
+/// this errors rather than overflowing
+pub fn days_after(time: Timestamp, days: u64) -> StdResult<Timestamp> {
+    let nanos = Uint64::from(days).checked_mul(Uint64::from(NANOS_IN_DAY))?;
+    let nanos = Uint64::from(time.nanos()).checked_add(nanos)?;
+    Ok(Timestamp::from_nanos(nanos.u64()))
+}
@@ -2,25 +2,41 @@
 mod tests {
     use crate::helpers::CwTemplateContract;
     use crate::msg::{
-        ExecuteMsg, InstantiateMsg, QueryMsg, WithdrawalReadyResponse, WithdrawalRequestedResponse,
+        AcceptedTokensResponse, Cw20BalanceCheck, Cw20BalanceResponse, Cw20DepositResponse,
+        DelegationsResponse, DepositsResponse, EscrowedNft, ExecuteMsg, InstantiateMsg,
+        KeeperPoolResponse, LastHeartbeatResponse, MigrateMsg, NftsResponse,
+        PendingConfigUpdateResponse, PendingWithdrawalResponse, ProposalsResponse, QueryMsg,
+        ReceiveMsg, SecurityStatusResponse, SupportedAssetsResponse, VaultBalanceResponse,
+        VerifyBalancesResponse, WithdrawalReadyResponse, WithdrawalRequestedResponse,
     };
-    use crate::state::Config;
+    use crate::state::{
+        Config, ConfigUpdate, ExecutionAllowList, ExecutionMsgType, GuardianKey, Proposal,
+        ProposalStatus, SignatureAlgorithm,
+    };
+    use crate::ContractError;
 
     use cw20_base::msg::InstantiateMsg as CW20InstantiateMsg;
+    use cw721_base::{
+        Cw721Contract, ExecuteMsg as Cw721BaseExecuteMsg, Extension,
+        InstantiateMsg as CW721InstantiateMsg, MintMsg, QueryMsg as Cw721BaseQueryMsg,
+    };
 
-    use cosmwasm_std::{coins, Addr, BlockInfo, Coin, Empty, Uint128};
+    use cosmwasm_std::{coins, to_binary, Addr, Binary, BlockInfo, Coin, Empty, Uint128};
     use cw_multi_test::{
         next_block, App, AppBuilder, AppResponse, Contract, ContractWrapper, Executor,
     };
 
-    use cw20::{Cw20Coin, MinterResponse};
+    use cosmwasm_storage::PrefixedStorage;
+    use cw20::{Cw20Coin, Cw20CoinVerified, MinterResponse};
+    use cw721::OwnerOfResponse;
 
     pub fn escrow_contract_template() -> Box<dyn Contract<Empty>> {
         let contract = ContractWrapper::new(
             crate::contract::execute,
             crate::contract::instantiate,
             crate::contract::query,
-        );
+        )
+        .with_migrate(crate::contract::migrate);
         Box::new(contract)
     }
 
@@ -33,6 +49,21 @@ mod tests {
         Box::new(contract)
     }
 
+    pub fn contract_cw721() -> Box<dyn Contract<Empty>> {
+        let contract = ContractWrapper::new(
+            |deps, env, info, msg: Cw721BaseExecuteMsg<Extension>| {
+                Cw721Contract::<Extension, Empty>::default().execute(deps, env, info, msg)
+            },
+            |deps, env, info, msg: CW721InstantiateMsg| {
+                Cw721Contract::<Extension, Empty>::default().instantiate(deps, env, info, msg)
+            },
+            |deps, env, msg: Cw721BaseQueryMsg| {
+                Cw721Contract::<Extension, Empty>::default().query(deps, env, msg)
+            },
+        );
+        Box::new(contract)
+    }
+
     const USER: &str = "user";
     //const ADMIN: &str = "ADMIN";
     const NATIVE_DENOM: &str = "ujuno";
@@ -55,29 +86,58 @@ mod tests {
         })
     }
 
-    fn mock_instantiate(
+    fn mock_instantiate_msg(
         days: u64,
         withdraw_immutable: bool,
         override_immutable: bool,
-    ) -> (App, CwTemplateContract, Addr, CwTemplateContract, Addr) {
-        let mut app = mock_app();
-        let escrow_contract_id = app.store_code(escrow_contract_template());
-        let cw20_id = app.store_code(contract_cw20());
-
+    ) -> InstantiateMsg {
         let withdraw_address = String::from(WITHDRAW_ADDRESS); // in reality this would be e.g. juno16g2rahf5846rxzp3fwlswy08fz8ccuwk03k57y
-        let _validated_addr = Addr::unchecked(&withdraw_address);
         let withdraw_delay_in_days = days; // this is what we are expecting to set it to
         let override_address = String::from(OVERRIDE_ADDRESS);
 
-        let msg = InstantiateMsg {
+        InstantiateMsg {
             set_withdraw_as_immutable: withdraw_immutable,
             set_override_as_immutable: override_immutable,
             enable_cw20_receive: false,
             override_address,
             withdraw_address,
             withdraw_delay_in_days,
-            native_denom: NATIVE_DENOM.to_string(),
-        };
+            native_denoms: vec![NATIVE_DENOM.to_string()],
+            recovery_address: None,
+            inactivity_period_in_days: None,
+            override_heartbeat_period_in_days: None,
+            fallback_override_address: None,
+            accepted_cw20_addresses: vec![],
+            unbonding_period_in_days: None,
+            execution_allow_list: None,
+            permissionless_execution: false,
+            keeper_tip: None,
+            guardian_key: None,
+            safe_harbor_address: None,
+            reserve_floors: vec![],
+            reserve_delay_in_days: None,
+            deposit_refund_period_in_days: None,
+        }
+    }
+
+    fn mock_instantiate(
+        days: u64,
+        withdraw_immutable: bool,
+        override_immutable: bool,
+    ) -> (App, CwTemplateContract, Addr, CwTemplateContract, Addr) {
+        mock_instantiate_with_msg(mock_instantiate_msg(
+            days,
+            withdraw_immutable,
+            override_immutable,
+        ))
+    }
+
+    fn mock_instantiate_with_msg(
+        msg: InstantiateMsg,
+    ) -> (App, CwTemplateContract, Addr, CwTemplateContract, Addr) {
+        let mut app = mock_app();
+        let escrow_contract_id = app.store_code(escrow_contract_template());
+        let cw20_id = app.store_code(contract_cw20());
 
         let escrow_contract_addr = app
             .instantiate_contract(
@@ -89,7 +149,7 @@ mod tests {
                     amount: Uint128::new(3_000_000),
                 }], // set a contract balance
                 "dao-escrow",
-                None,
+                Some(USER.to_string()), // in reality we would set --no-admin, this is for migrations
             )
             .unwrap();
 
@@ -106,10 +166,16 @@ mod tests {
             }),
             marketing: None,
             decimals: 6,
-            initial_balances: vec![Cw20Coin {
-                address: escrow_contract_addr.to_string(),
-                amount: Uint128::new(5_000_000),
-            }],
+            initial_balances: vec![
+                Cw20Coin {
+                    address: escrow_contract_addr.to_string(),
+                    amount: Uint128::new(5_000_000),
+                },
+                Cw20Coin {
+                    address: USER.to_string(),
+                    amount: Uint128::new(1_000_000),
+                },
+            ],
         };
 
         let cw20_contract_addr = app
@@ -214,6 +280,10 @@ mod tests {
         let msg = ExecuteMsg::StartWithdraw {
             amount,
             denom_or_address,
+            auto_undelegate: false,
+            use_reserve_delay: false,
+            vault: None,
+            category: None,
         };
 
         app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
@@ -229,11 +299,107 @@ mod tests {
         let msg = ExecuteMsg::StartWithdraw {
             amount,
             denom_or_address: cw20_contract_address,
+            auto_undelegate: false,
+            use_reserve_delay: false,
+            vault: None,
+            category: None,
+        };
+
+        app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
+    }
+
+    fn start_recovery_withdraw(
+        app: &mut App,
+        address: String,
+        contract_address: Addr,
+        amount: Uint128,
+        denom_or_address: String,
+    ) -> anyhow::Result<AppResponse> {
+        let msg = ExecuteMsg::StartRecoveryWithdraw {
+            amount,
+            denom_or_address,
         };
 
         app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
     }
 
+    fn exec_heartbeat(
+        app: &mut App,
+        address: String,
+        contract_address: Addr,
+    ) -> anyhow::Result<AppResponse> {
+        let msg = ExecuteMsg::Heartbeat {};
+
+        app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
+    }
+
+    // proposes a config update, waits out the delay and applies it
+    fn exec_config_update(app: &mut App, contract_address: Addr, update: ConfigUpdate) {
+        let msg = ExecuteMsg::StartConfigUpdate { update };
+        app.execute_contract(
+            Addr::unchecked(OVERRIDE_ADDRESS),
+            contract_address.clone(),
+            &msg,
+            &[],
+        )
+        .unwrap();
+
+        let delay_in_days = get_config(app, contract_address.clone())
+            .unwrap()
+            .withdraw_delay_in_days;
+        for _ in 0..delay_in_days {
+            app.update_block(advance_one_day_one_hour);
+        }
+
+        let msg = ExecuteMsg::ExecuteConfigUpdate {};
+        app.execute_contract(
+            Addr::unchecked(OVERRIDE_ADDRESS),
+            contract_address,
+            &msg,
+            &[],
+        )
+        .unwrap();
+    }
+
+    // overwrites the cw2 version the contract has stored
+    // as if it had been deployed as an older version
+    fn set_stored_version(app: &mut App, contract_address: &Addr, version: &str) {
+        app.init_modules(|_, _, storage| {
+            let namespace = [b"contract_data/".as_slice(), contract_address.as_bytes()].concat();
+            let mut storage = PrefixedStorage::multilevel(storage, &[b"wasm", &namespace]);
+            cw2::set_contract_version(&mut storage, "crates.io:dao-escrow", version).unwrap();
+        });
+    }
+
+    fn send_cw20(
+        app: &mut App,
+        cw20_addr: Addr,
+        contract_address: Addr,
+        amount: Uint128,
+        msg: Binary,
+    ) -> anyhow::Result<AppResponse> {
+        let msg = cw20::Cw20ExecuteMsg::Send {
+            contract: contract_address.to_string(),
+            amount,
+            msg,
+        };
+
+        app.execute_contract(Addr::unchecked(USER), cw20_addr, &msg, &[])
+    }
+
+    fn get_escrowed_cw20_balance(
+        app: &mut App,
+        contract_address: Addr,
+        cw20_addr: Addr,
+    ) -> Uint128 {
+        let msg = QueryMsg::GetCw20Balance {
+            address: cw20_addr.to_string(),
+        };
+        let result: Cw20BalanceResponse =
+            app.wrap().query_wasm_smart(contract_address, &msg).unwrap();
+        result.balance
+    }
+
     fn get_cw20_balance(app: &mut App, cw20_addr: Addr, address: String) -> Uint128 {
         let msg = cw20_base::msg::QueryMsg::Balance { address };
         let result: cw20::BalanceResponse = app.wrap().query_wasm_smart(cw20_addr, &msg).unwrap();
@@ -655,7 +821,7 @@ mod tests {
 
             let config = get_config(&mut app, contract_addr.clone()).unwrap();
 
-            assert_eq!(config.override_address, OVERRIDE_ADDRESS);
+            assert_eq!(config.override_address.unwrap(), OVERRIDE_ADDRESS);
 
             // but this sender is legit
             // however override is set as immutable
@@ -671,7 +837,7 @@ mod tests {
             let config_two = get_config(&mut app, contract_addr).unwrap();
             assert_eq!(
                 config_two.override_address,
-                Addr::unchecked(OVERRIDE_ADDRESS)
+                Some(Addr::unchecked(OVERRIDE_ADDRESS))
             );
         }
 
@@ -692,7 +858,7 @@ mod tests {
 
             let config = get_config(&mut app, contract_addr.clone()).unwrap();
 
-            assert_eq!(config.override_address, OVERRIDE_ADDRESS);
+            assert_eq!(config.override_address.unwrap(), OVERRIDE_ADDRESS);
 
             // but this is legit
             exec_update_override_address(
@@ -704,7 +870,10 @@ mod tests {
             .unwrap();
 
             let config_two = get_config(&mut app, contract_addr).unwrap();
-            assert_eq!(config_two.override_address, Addr::unchecked(new_address));
+            assert_eq!(
+                config_two.override_address,
+                Some(Addr::unchecked(new_address))
+            );
         }
 
         #[test]
@@ -756,4 +925,5306 @@ mod tests {
             assert_eq!(config.withdraw_address, WITHDRAW_ADDRESS);
         }
     }
+
+    mod recovery {
+        use super::*;
+
+        const RECOVERY_ADDRESS: &str = "recovery-cold-multisig-address";
+
+        // 1 day withdraw delay, recovery after 2 days of silence
+        fn mock_instantiate_with_recovery() -> (App, CwTemplateContract, Addr) {
+            let msg = InstantiateMsg {
+                recovery_address: Some(RECOVERY_ADDRESS.to_string()),
+                inactivity_period_in_days: Some(2),
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (app, cw_template_contract, contract_addr, _, _) = mock_instantiate_with_msg(msg);
+            (app, cw_template_contract, contract_addr)
+        }
+
+        #[test]
+        fn recovery_config_must_be_complete() {
+            let msg = InstantiateMsg {
+                recovery_address: Some(RECOVERY_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, true, true)
+            };
+
+            let mut app = mock_app();
+            let escrow_contract_id = app.store_code(escrow_contract_template());
+            app.instantiate_contract(
+                escrow_contract_id,
+                Addr::unchecked(USER),
+                &msg,
+                &[],
+                "dao-escrow",
+                None,
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn recovery_withdraw_after_inactivity() {
+            let (mut app, cw_template_contract, contract_addr) = mock_instantiate_with_recovery();
+            let recovery_addr = Addr::unchecked(RECOVERY_ADDRESS);
+
+            // withdraw address is still around, so no dice
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap_err();
+
+            // the withdraw address goes quiet for 3 days
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+
+            // randoms can't use the recovery flow
+            start_recovery_withdraw(
+                &mut app,
+                "some-random-address".to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap_err();
+
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            // still subject to the delay
+            let claim_msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            let claim_msg_res = cw_template_contract.call(claim_msg).unwrap();
+            app.execute(recovery_addr.clone(), claim_msg_res.clone())
+                .unwrap_err();
+
+            app.update_block(advance_one_day_one_hour);
+
+            // the withdraw address can't claim a recovery withdrawal
+            app.execute(Addr::unchecked(WITHDRAW_ADDRESS), claim_msg_res.clone())
+                .unwrap_err();
+
+            app.execute(recovery_addr.clone(), claim_msg_res).unwrap();
+
+            let recovery_balance = get_balance(&mut app, &recovery_addr);
+            assert_eq!(recovery_balance, coins(2_000_000, NATIVE_DENOM));
+
+            let contract_balance = get_balance(&mut app, &contract_addr);
+            assert_eq!(contract_balance, coins(1_000_000, NATIVE_DENOM));
+        }
+
+        #[test]
+        fn override_cancels_recovery_withdraw() {
+            let (mut app, cw_template_contract, contract_addr) = mock_instantiate_with_recovery();
+
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            exec_override(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let claim_msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            let claim_msg_res = cw_template_contract.call(claim_msg).unwrap();
+            app.execute(Addr::unchecked(RECOVERY_ADDRESS), claim_msg_res)
+                .unwrap_err();
+
+            let contract_balance = get_balance(&mut app, &contract_addr);
+            assert_eq!(contract_balance, coins(3_000_000, NATIVE_DENOM));
+        }
+
+        #[test]
+        fn heartbeat_cancels_recovery_withdraw() {
+            let (mut app, _cw_template_contract, contract_addr) = mock_instantiate_with_recovery();
+
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            // only the withdraw address can check in
+            exec_heartbeat(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap_err();
+
+            // turns out the DAO was just on holiday
+            exec_heartbeat(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap();
+
+            let withdrawal_requested_res =
+                withdrawal_requested(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(
+                withdrawal_requested_res,
+                WithdrawalRequestedResponse {
+                    withdrawal_requested: false,
+                }
+            );
+
+            let heartbeat: LastHeartbeatResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetLastHeartbeat {})
+                .unwrap();
+            assert_eq!(heartbeat.last_heartbeat, app.block_info().time);
+            assert_eq!(
+                heartbeat.recovery_available_at,
+                Some(app.block_info().time.plus_seconds(2 * 86400))
+            );
+
+            // and the timer has been reset
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr,
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap_err();
+        }
+    }
+
+    mod override_liveness {
+        use super::*;
+
+        use crate::state::{Asset, LockableField};
+
+        const FALLBACK_ADDRESS: &str = "fallback-guardian-address";
+        const SAFE_HARBOR_ADDRESS: &str = "safe-harbor-cold-multisig-address";
+
+        fn get_security_status(app: &mut App, contract_address: Addr) -> SecurityStatusResponse {
+            app.wrap()
+                .query_wasm_smart(contract_address, &QueryMsg::GetSecurityStatus {})
+                .unwrap()
+        }
+
+        #[test]
+        fn fallback_requires_heartbeat_period() {
+            let msg = InstantiateMsg {
+                fallback_override_address: Some(FALLBACK_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, true, true)
+            };
+
+            let mut app = mock_app();
+            let escrow_contract_id = app.store_code(escrow_contract_template());
+            app.instantiate_contract(
+                escrow_contract_id,
+                Addr::unchecked(USER),
+                &msg,
+                &[],
+                "dao-escrow",
+                None,
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn fallback_takes_over_when_override_lapses() {
+            let msg = InstantiateMsg {
+                override_heartbeat_period_in_days: Some(1),
+                fallback_override_address: Some(FALLBACK_ADDRESS.to_string()),
+                ..mock_instantiate_msg(28, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            let status = get_security_status(&mut app, contract_addr.clone());
+            assert!(!status.override_address_lapsed);
+            assert_eq!(
+                status.active_override_address,
+                Some(Addr::unchecked(OVERRIDE_ADDRESS))
+            );
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            // the dev multisig goes quiet
+            app.update_block(advance_one_day_one_hour);
+
+            let status = get_security_status(&mut app, contract_addr.clone());
+            assert!(status.override_address_lapsed);
+            assert!(!status.withdraw_address_inactive);
+            assert_eq!(
+                status.active_override_address,
+                Some(Addr::unchecked(FALLBACK_ADDRESS))
+            );
+
+            // the lapsed override has lost its veto
+            exec_override(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap_err();
+
+            // but the fallback has it
+            exec_override(
+                &mut app,
+                FALLBACK_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap();
+
+            // the original checks back in and takes its powers back
+            exec_heartbeat(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap();
+
+            let status = get_security_status(&mut app, contract_addr.clone());
+            assert!(!status.override_address_lapsed);
+            assert_eq!(
+                status.active_override_address,
+                Some(Addr::unchecked(OVERRIDE_ADDRESS))
+            );
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            exec_override(
+                &mut app,
+                FALLBACK_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap_err();
+
+            exec_override(&mut app, OVERRIDE_ADDRESS.to_string(), contract_addr).unwrap();
+        }
+
+        #[test]
+        fn lapsed_override_without_fallback_is_flagged() {
+            let msg = InstantiateMsg {
+                override_heartbeat_period_in_days: Some(1),
+                ..mock_instantiate_msg(28, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let status = get_security_status(&mut app, contract_addr.clone());
+            assert!(status.override_address_lapsed);
+            assert_eq!(
+                status.active_override_address,
+                Some(Addr::unchecked(OVERRIDE_ADDRESS))
+            );
+
+            // with nobody to hand over to, the override keeps its powers
+            exec_override(&mut app, OVERRIDE_ADDRESS.to_string(), contract_addr).unwrap();
+        }
+
+        #[test]
+        fn fallback_only_cancels_withdrawals_and_updates_addresses() {
+            let msg = InstantiateMsg {
+                override_heartbeat_period_in_days: Some(1),
+                fallback_override_address: Some(FALLBACK_ADDRESS.to_string()),
+                safe_harbor_address: Some(SAFE_HARBOR_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+            app.update_block(advance_one_day_one_hour);
+
+            let execute = |app: &mut App, sender: &str, msg: ExecuteMsg| {
+                app.execute_contract(Addr::unchecked(sender), contract_addr.clone(), &msg, &[])
+            };
+
+            // the other override powers stay with the override_address
+            let evacuate = ExecuteMsg::Evacuate {
+                assets: vec![Asset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(1_000_000),
+                }],
+            };
+            let lock = ExecuteMsg::LockField {
+                field: LockableField::Cw20Receive,
+            };
+            let update = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::SetPermissionlessExecution { enabled: true },
+            };
+            for msg in [
+                evacuate.clone(),
+                lock,
+                update,
+                ExecuteMsg::RenounceOverride {},
+            ] {
+                let err = execute(&mut app, FALLBACK_ADDRESS, msg).unwrap_err();
+                assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            }
+            execute(&mut app, OVERRIDE_ADDRESS, evacuate).unwrap();
+
+            // the fallback can update the withdraw_address
+            execute(
+                &mut app,
+                FALLBACK_ADDRESS,
+                ExecuteMsg::UpdateWithdrawalAddress {
+                    address: WITHDRAW_ADDRESS.to_string(),
+                },
+            )
+            .unwrap();
+
+            // and hand the override role on
+            execute(
+                &mut app,
+                FALLBACK_ADDRESS,
+                ExecuteMsg::UpdateOverrideAddress {
+                    address: FALLBACK_ADDRESS.to_string(),
+                },
+            )
+            .unwrap();
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(
+                config.override_address,
+                Some(Addr::unchecked(FALLBACK_ADDRESS))
+            );
+        }
+    }
+
+    mod receive {
+        use super::*;
+
+        fn mock_instantiate_with_receive() -> (App, CwTemplateContract, Addr, Addr) {
+            let msg = InstantiateMsg {
+                enable_cw20_receive: true,
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (mut app, cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            (app, cw_template_contract, contract_addr, cw20_contract_addr)
+        }
+
+        #[test]
+        fn receive_disabled() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, true, true);
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap_err();
+
+            // tokens stay with the user
+            let user_balance = get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string());
+            assert_eq!(user_balance, Uint128::new(1_000_000));
+        }
+
+        #[test]
+        fn receive_credits_token_contract() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(600_000),
+                Binary::default(),
+            )
+            .unwrap();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(400_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // credited against the cw20 contract
+            let escrowed_balance = get_escrowed_cw20_balance(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.clone(),
+            );
+            assert_eq!(escrowed_balance, Uint128::new(1_000_000));
+
+            // not the user who sent it
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr.clone(), Addr::unchecked(USER));
+            assert_eq!(escrowed_balance, Uint128::zero());
+
+            // but we know who sent it
+            let deposit: Cw20DepositResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetCw20Deposit {
+                        address: cw20_contract_addr.to_string(),
+                        depositor: USER.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(deposit.amount, Uint128::new(1_000_000));
+
+            // the original 5_000_000 plus what was sent
+            let contract_cw20_balance =
+                get_cw20_balance(&mut app, cw20_contract_addr, contract_addr.to_string());
+            assert_eq!(contract_cw20_balance, Uint128::new(6_000_000));
+        }
+
+        #[test]
+        fn receive_with_memo_and_vault() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                to_binary(&ReceiveMsg::DepositWithMemo {
+                    memo: "grant repayment".to_string(),
+                })
+                .unwrap(),
+            )
+            .unwrap();
+
+            // only a vault that's been set up can take a deposit
+            let deposit_to_cold = to_binary(&ReceiveMsg::DepositToVault {
+                vault: "cold".to_string(),
+            })
+            .unwrap();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1),
+                deposit_to_cold.clone(),
+            )
+            .unwrap_err();
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetVault {
+                    name: "cold".to_string(),
+                    delay_in_days: 5,
+                    limits: vec![],
+                },
+            );
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(200_000),
+                deposit_to_cold,
+            )
+            .unwrap();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(300_000),
+                to_binary(&ReceiveMsg::Deposit {}).unwrap(),
+            )
+            .unwrap();
+
+            let deposits: DepositsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::ListDeposits {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(deposits.deposits.len(), 3);
+            assert_eq!(
+                deposits.deposits[0].memo,
+                Some("grant repayment".to_string())
+            );
+            assert_eq!(deposits.deposits[1].vault, Some("cold".to_string()));
+            assert_eq!(deposits.deposits[2].amount, Uint128::new(300_000));
+            assert_eq!(deposits.deposits[2].depositor, Addr::unchecked(USER));
+
+            let vault_balance: VaultBalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetVaultBalance {
+                        vault: Some("cold".to_string()),
+                        denom_or_address: cw20_contract_addr.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(vault_balance.balance, Uint128::new(200_000));
+
+            // the default vault is everything else, including the untracked 5_000_000
+            let vault_balance: VaultBalanceResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetVaultBalance {
+                        vault: None,
+                        denom_or_address: cw20_contract_addr.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(vault_balance.balance, Uint128::new(5_400_000));
+
+            // and a vault nobody has heard of isn't just empty
+            app.wrap()
+                .query_wasm_smart::<VaultBalanceResponse>(
+                    contract_addr.clone(),
+                    &QueryMsg::GetVaultBalance {
+                        vault: Some("cdl".to_string()),
+                        denom_or_address: cw20_contract_addr.to_string(),
+                    },
+                )
+                .unwrap_err();
+
+            // the vault is a slice of the ledger, not on top of it
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr, cw20_contract_addr);
+            assert_eq!(escrowed_balance, Uint128::new(600_000));
+        }
+
+        #[test]
+        fn receive_rejects_unrecognised_msg() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::from(br#"{"buy_me_a_yacht":{}}"#.to_vec()),
+            )
+            .unwrap_err();
+
+            // memos are capped too
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                to_binary(&ReceiveMsg::DepositWithMemo {
+                    memo: "a".repeat(257),
+                })
+                .unwrap(),
+            )
+            .unwrap_err();
+
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr, cw20_contract_addr.clone());
+            assert_eq!(escrowed_balance, Uint128::zero());
+
+            let user_balance = get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string());
+            assert_eq!(user_balance, Uint128::new(1_000_000));
+        }
+
+        #[test]
+        fn deposit_cw20_via_allowance() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            let deposit_msg = ExecuteMsg::DepositCw20 {
+                token: cw20_contract_addr.to_string(),
+                amount: Uint128::new(500_000),
+            };
+
+            // no allowance, no deposit
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &deposit_msg,
+                &[],
+            )
+            .unwrap_err();
+
+            let allowance_msg = cw20::Cw20ExecuteMsg::IncreaseAllowance {
+                spender: contract_addr.to_string(),
+                amount: Uint128::new(500_000),
+                expires: None,
+            };
+            app.execute_contract(
+                Addr::unchecked(USER),
+                cw20_contract_addr.clone(),
+                &allowance_msg,
+                &[],
+            )
+            .unwrap();
+
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &deposit_msg,
+                &[],
+            )
+            .unwrap();
+
+            let escrowed_balance = get_escrowed_cw20_balance(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.clone(),
+            );
+            assert_eq!(escrowed_balance, Uint128::new(500_000));
+
+            let deposit: Cw20DepositResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::GetCw20Deposit {
+                        address: cw20_contract_addr.to_string(),
+                        depositor: USER.to_string(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(deposit.amount, Uint128::new(500_000));
+
+            let user_balance =
+                get_cw20_balance(&mut app, cw20_contract_addr.clone(), USER.to_string());
+            assert_eq!(user_balance, Uint128::new(500_000));
+
+            // the allowance is used up
+            app.execute_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &deposit_msg,
+                &[],
+            )
+            .unwrap_err();
+
+            // and the allow-list applies here too
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(USER),
+                    contract_addr,
+                    &ExecuteMsg::DepositCw20 {
+                        token: "some-random-address".to_string(),
+                        amount: Uint128::new(500_000),
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::CW20TokenNotAccepted {},
+                err.downcast().unwrap()
+            );
+        }
+
+        #[test]
+        fn escrowed_cw20_withdraw() {
+            let (mut app, cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            start_cw20_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(400_000),
+                cw20_contract_addr.to_string(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let claim_msg = ExecuteMsg::ExecuteEscrowCW20Withdraw {
+                address: cw20_contract_addr.to_string(),
+            };
+            let claim_msg_res = cw_template_contract.call(claim_msg).unwrap();
+            app.execute(Addr::unchecked(WITHDRAW_ADDRESS), claim_msg_res)
+                .unwrap();
+
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr, cw20_contract_addr.clone());
+            assert_eq!(escrowed_balance, Uint128::new(600_000));
+
+            let withdrawer_balance =
+                get_cw20_balance(&mut app, cw20_contract_addr, WITHDRAW_ADDRESS.to_string());
+            assert_eq!(withdrawer_balance, Uint128::new(400_000));
+        }
+
+        #[test]
+        fn cw20_withdraw_debits_tracked_then_untracked() {
+            let (mut app, cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            // 1_000_000 tracked, plus the 5_000_000 untracked
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            start_cw20_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(3_000_000),
+                cw20_contract_addr.to_string(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let claim_msg = ExecuteMsg::ExecuteCW20Withdraw {
+                address: cw20_contract_addr.to_string(),
+            };
+            let claim_msg_res = cw_template_contract.call(claim_msg).unwrap();
+            let res = app
+                .execute(Addr::unchecked(WITHDRAW_ADDRESS), claim_msg_res.clone())
+                .unwrap();
+
+            let wasm = res.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+            let attribute = |key: &str| {
+                wasm.attributes
+                    .iter()
+                    .find(|attr| attr.key == key)
+                    .unwrap()
+                    .value
+                    .clone()
+            };
+            assert_eq!(attribute("tracked_amount"), "1000000");
+            assert_eq!(attribute("untracked_amount"), "2000000");
+
+            // the ledger can't be spent twice
+            let escrowed_balance = get_escrowed_cw20_balance(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.clone(),
+            );
+            assert_eq!(escrowed_balance, Uint128::zero());
+
+            // only 3_000_000 left, so this can't be paid
+            start_cw20_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(4_000_000),
+                cw20_contract_addr.to_string(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let err = app
+                .execute(Addr::unchecked(WITHDRAW_ADDRESS), claim_msg_res)
+                .unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientContractBalance {},
+                err.downcast().unwrap()
+            );
+
+            let withdrawer_balance =
+                get_cw20_balance(&mut app, cw20_contract_addr, WITHDRAW_ADDRESS.to_string());
+            assert_eq!(withdrawer_balance, Uint128::new(3_000_000));
+        }
+
+        #[test]
+        fn sync_cw20_balance_reconciles_surplus() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // the 5_000_000 minted straight to the contract was never tracked
+            let verified: VerifyBalancesResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::VerifyBalances {})
+                .unwrap();
+            assert_eq!(
+                verified.balances,
+                vec![Cw20BalanceCheck {
+                    address: cw20_contract_addr.clone(),
+                    ledger_balance: Uint128::new(1_000_000),
+                    actual_balance: Uint128::new(6_000_000),
+                    surplus: Uint128::new(5_000_000),
+                    deficit: Uint128::zero(),
+                }]
+            );
+
+            let sync_msg = ExecuteMsg::SyncCw20Balance {
+                address: cw20_contract_addr.to_string(),
+            };
+
+            // only the override can reconcile
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &sync_msg,
+                &[],
+            )
+            .unwrap_err();
+
+            let res = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &sync_msg,
+                    &[],
+                )
+                .unwrap();
+            let wasm = res.events.iter().find(|ev| ev.ty == "wasm").unwrap();
+            assert!(wasm
+                .attributes
+                .iter()
+                .any(|attr| attr.key == "surplus" && attr.value == "5000000"));
+
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr.clone(), cw20_contract_addr);
+            assert_eq!(escrowed_balance, Uint128::new(6_000_000));
+
+            let verified: VerifyBalancesResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::VerifyBalances {})
+                .unwrap();
+            assert_eq!(verified.balances[0].surplus, Uint128::zero());
+            assert_eq!(verified.balances[0].deficit, Uint128::zero());
+
+            // and won't start tracking just anything
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr,
+                &ExecuteMsg::SyncCw20Balance {
+                    address: "some-random-address".to_string(),
+                },
+                &[],
+            )
+            .unwrap_err();
+        }
+
+        #[test]
+        fn migrate_rebuilds_cw20_balances() {
+            let (mut app, _cw_template_contract, contract_addr, cw20_contract_addr) =
+                mock_instantiate_with_receive();
+
+            // a direct call to the hook, as an old version would have keyed it
+            // this needs the caller to be accepted now, so pretend it was
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: USER.to_string(),
+                },
+            );
+            let forged = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                sender: USER.to_string(),
+                amount: Uint128::new(9_000_000),
+                msg: Binary::default(),
+            });
+            app.execute_contract(Addr::unchecked(USER), contract_addr.clone(), &forged, &[])
+                .unwrap();
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr.clone(), Addr::unchecked(USER));
+            assert_eq!(escrowed_balance, Uint128::new(9_000_000));
+
+            // and make it look like it was deployed as an old version
+            set_stored_version(&mut app, &contract_addr, "0.2.0");
+
+            // only the admin can migrate
+            let escrow_code_id = app.store_code(escrow_contract_template());
+            let migrate_msg = MigrateMsg {
+                cw20_addresses: vec![cw20_contract_addr.to_string()],
+                native_denoms: vec![],
+            };
+            app.migrate_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &migrate_msg,
+                escrow_code_id,
+            )
+            .unwrap();
+
+            // the stale entry is gone
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr.clone(), Addr::unchecked(USER));
+            assert_eq!(escrowed_balance, Uint128::zero());
+
+            // and the cw20 is tracked at what we actually hold
+            let escrowed_balance = get_escrowed_cw20_balance(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.clone(),
+            );
+            assert_eq!(escrowed_balance, Uint128::new(5_000_000));
+
+            // and accepted, so it can still be topped up
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // a later migrate leaves the ledger alone, even with nothing listed
+            let migrate_msg = MigrateMsg {
+                cw20_addresses: vec![],
+                native_denoms: vec![],
+            };
+            app.migrate_contract(
+                Addr::unchecked(USER),
+                contract_addr.clone(),
+                &migrate_msg,
+                escrow_code_id,
+            )
+            .unwrap();
+            let escrowed_balance =
+                get_escrowed_cw20_balance(&mut app, contract_addr, cw20_contract_addr);
+            assert_eq!(escrowed_balance, Uint128::new(5_001_000));
+        }
+    }
+
+    mod config_update {
+        use super::*;
+
+        fn list_accepted_tokens(app: &mut App, contract_address: Addr) -> Vec<Addr> {
+            let msg = QueryMsg::ListAcceptedTokens {
+                start_after: None,
+                limit: None,
+            };
+            let result: AcceptedTokensResponse =
+                app.wrap().query_wasm_smart(contract_address, &msg).unwrap();
+            result.tokens
+        }
+
+        #[test]
+        fn receive_rejects_unaccepted_token() {
+            let msg = InstantiateMsg {
+                enable_cw20_receive: true,
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+
+            let err = send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::CW20TokenNotAccepted {},
+                err.downcast().unwrap()
+            );
+
+            // nor can anyone forge a deposit by calling the hook directly
+            let forged = ExecuteMsg::Receive(cw20::Cw20ReceiveMsg {
+                sender: USER.to_string(),
+                amount: Uint128::new(9_000_000),
+                msg: Binary::default(),
+            });
+            app.execute_contract(Addr::unchecked(USER), contract_addr.clone(), &forged, &[])
+                .unwrap_err();
+
+            assert!(list_accepted_tokens(&mut app, contract_addr.clone()).is_empty());
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            assert_eq!(
+                list_accepted_tokens(&mut app, contract_addr.clone()),
+                vec![cw20_contract_addr.clone()]
+            );
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::RemoveAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            assert!(list_accepted_tokens(&mut app, contract_addr).is_empty());
+        }
+
+        #[test]
+        fn enable_cw20_receive_via_config_update() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, true, true);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            // accepted, but receive is still off
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap_err();
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetCw20Receive { enabled: true },
+            );
+
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert!(config.enable_cw20_receive);
+
+            send_cw20(
+                &mut app,
+                cw20_contract_addr,
+                contract_addr,
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn config_update_is_delayed_and_cancellable() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, true, true);
+
+            let start_msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            };
+
+            // only the override can propose
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &start_msg,
+                &[],
+            )
+            .unwrap_err();
+
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &start_msg,
+                &[],
+            )
+            .unwrap();
+
+            let pending: PendingConfigUpdateResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetPendingConfigUpdate {})
+                .unwrap();
+            assert!(pending.pending_config_update.is_some());
+
+            // not ready yet
+            let execute_msg = ExecuteMsg::ExecuteConfigUpdate {};
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &execute_msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::ConfigUpdateNotReady {},
+                err.downcast().unwrap()
+            );
+
+            // the DAO doesn't like it
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::CancelConfigUpdate {},
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &execute_msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::ConfigUpdateNotRequested {},
+                err.downcast().unwrap()
+            );
+
+            assert!(list_accepted_tokens(&mut app, contract_addr).is_empty());
+        }
+
+        #[test]
+        fn delays_are_bounded() {
+            let msg = InstantiateMsg {
+                withdraw_delay_in_days: 3651,
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let mut app = mock_app();
+            let escrow_contract_id = app.store_code(escrow_contract_template());
+            let err = app
+                .instantiate_contract(
+                    escrow_contract_id,
+                    Addr::unchecked(USER),
+                    &msg,
+                    &[],
+                    "dao-escrow",
+                    None,
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidDelay {}, err.downcast().unwrap());
+
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            for update in [
+                ConfigUpdate::SetWithdrawDelay { days: 3651 },
+                ConfigUpdate::SetWithdrawDelay { days: u64::MAX / 2 },
+                ConfigUpdate::SetReserveDelay { days: Some(3651) },
+            ] {
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(OVERRIDE_ADDRESS),
+                        contract_addr.clone(),
+                        &ExecuteMsg::StartConfigUpdate { update },
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(ContractError::InvalidDelay {}, err.downcast().unwrap());
+            }
+
+            // the longest delay still leaves config updates working
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetWithdrawDelay { days: 3650 },
+            );
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr,
+                &ExecuteMsg::StartConfigUpdate {
+                    update: ConfigUpdate::SetWithdrawDelay { days: 1 },
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn one_config_update_at_a_time() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, true, true);
+
+            let start_msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &start_msg,
+                &[],
+            )
+            .unwrap();
+
+            // can't be swapped out for something else
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &ExecuteMsg::StartConfigUpdate {
+                        update: ConfigUpdate::SetCw20Receive { enabled: false },
+                    },
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::ConfigUpdatePending {},
+                err.downcast().unwrap()
+            );
+
+            // the first one is still the one pending
+            app.update_block(advance_one_day_one_hour);
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::ExecuteConfigUpdate {},
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                list_accepted_tokens(&mut app, contract_addr.clone()),
+                vec![cw20_contract_addr.to_string()]
+            );
+
+            // and once it's applied, the next can start
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr,
+                &ExecuteMsg::StartConfigUpdate {
+                    update: ConfigUpdate::SetCw20Receive { enabled: false },
+                },
+                &[],
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn address_changes_throw_out_a_pending_config_update() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, false, false);
+
+            let start_msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            };
+            let pending = |app: &mut App| -> bool {
+                let res: PendingConfigUpdateResponse = app
+                    .wrap()
+                    .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetPendingConfigUpdate {})
+                    .unwrap();
+                res.pending_config_update.is_some()
+            };
+
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &start_msg,
+                &[],
+            )
+            .unwrap();
+            assert!(pending(&mut app));
+
+            let res = exec_update_withdraw_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                "new_withdraw".to_string(),
+            )
+            .unwrap();
+            assert!(res.events.iter().any(|e| e
+                .attributes
+                .iter()
+                .any(|a| a.key == "config_update_cancelled" && a.value == "true")));
+            assert!(!pending(&mut app));
+
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &start_msg,
+                &[],
+            )
+            .unwrap();
+            assert!(pending(&mut app));
+
+            exec_update_override_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                "new_override".to_string(),
+            )
+            .unwrap();
+            assert!(!pending(&mut app));
+
+            // nothing left to apply
+            app.update_block(advance_one_day_one_hour);
+            let err = app
+                .execute_contract(
+                    Addr::unchecked("new_override"),
+                    contract_addr.clone(),
+                    &ExecuteMsg::ExecuteConfigUpdate {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::ConfigUpdateNotRequested {},
+                err.downcast().unwrap()
+            );
+            assert!(list_accepted_tokens(&mut app, contract_addr).is_empty());
+        }
+    }
+
+    mod native_denoms {
+        use super::*;
+
+        const IBC_DENOM: &str =
+            "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+        #[test]
+        fn start_withdraw_rejects_unsupported_denom() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            let err = start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                IBC_DENOM.to_string(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::UnsupportedDenom {
+                    denom: IBC_DENOM.to_string()
+                },
+                err.downcast().unwrap()
+            );
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddNativeDenom {
+                    denom: IBC_DENOM.to_string(),
+                },
+            );
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr,
+                Uint128::new(1_000_000),
+                IBC_DENOM.to_string(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn malformed_denoms_are_rejected() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            for denom in [
+                "ibc/not-a-hash",
+                "ibc/27394fb092d2eccd56123c74f36e4c1f926001ceada9ca97ea622b25f41e5eb2",
+                "factory/",
+                "factory/creator-address/",
+                "1juno",
+                "u",
+            ] {
+                let msg = ExecuteMsg::StartConfigUpdate {
+                    update: ConfigUpdate::AddNativeDenom {
+                        denom: denom.to_string(),
+                    },
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(OVERRIDE_ADDRESS),
+                        contract_addr.clone(),
+                        &msg,
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    ContractError::InvalidDenom {
+                        denom: denom.to_string()
+                    },
+                    err.downcast().unwrap()
+                );
+            }
+
+            // tokenfactory denoms are fine
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::AddNativeDenom {
+                    denom: "factory/creator-address/uhowl".to_string(),
+                },
+            };
+            app.execute_contract(Addr::unchecked(OVERRIDE_ADDRESS), contract_addr, &msg, &[])
+                .unwrap();
+        }
+
+        #[test]
+        fn list_supported_assets() {
+            let msg = InstantiateMsg {
+                native_denoms: vec![NATIVE_DENOM.to_string(), IBC_DENOM.to_string()],
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            let assets: SupportedAssetsResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::ListSupportedAssets {})
+                .unwrap();
+
+            assert_eq!(
+                assets.native,
+                vec![
+                    Coin {
+                        denom: IBC_DENOM.to_string(),
+                        amount: Uint128::zero(),
+                    },
+                    Coin {
+                        denom: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(3_000_000),
+                    },
+                ]
+            );
+            assert_eq!(
+                assets.cw20,
+                vec![Cw20CoinVerified {
+                    address: cw20_contract_addr,
+                    amount: Uint128::new(5_000_000),
+                }]
+            );
+        }
+    }
+
+    mod nft {
+        use super::*;
+
+        use crate::state::Proposal;
+        use cosmwasm_std::{CosmosMsg, WasmMsg};
+
+        const COLLECTION_TOKEN_IDS: [&str; 2] = ["howl.juno", "badge-1"];
+
+        // instantiates an escrow and a CW721 collection,
+        // and sends every token in the collection to the escrow
+        fn mock_instantiate_with_nfts() -> (App, Addr, Addr) {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            let cw721_id = app.store_code(contract_cw721());
+            let collection_addr = app
+                .instantiate_contract(
+                    cw721_id,
+                    Addr::unchecked(USER),
+                    &CW721InstantiateMsg {
+                        name: "Howl Names".to_string(),
+                        symbol: "HNS".to_string(),
+                        minter: USER.to_string(),
+                    },
+                    &[],
+                    "cw721",
+                    None,
+                )
+                .unwrap();
+
+            for token_id in COLLECTION_TOKEN_IDS {
+                let mint_msg: Cw721BaseExecuteMsg<Extension> = Cw721BaseExecuteMsg::Mint(MintMsg {
+                    token_id: token_id.to_string(),
+                    owner: USER.to_string(),
+                    token_uri: None,
+                    extension: None,
+                });
+                app.execute_contract(
+                    Addr::unchecked(USER),
+                    collection_addr.clone(),
+                    &mint_msg,
+                    &[],
+                )
+                .unwrap();
+
+                let send_msg: Cw721BaseExecuteMsg<Extension> = Cw721BaseExecuteMsg::SendNft {
+                    contract: contract_addr.to_string(),
+                    token_id: token_id.to_string(),
+                    msg: Binary::default(),
+                };
+                app.execute_contract(
+                    Addr::unchecked(USER),
+                    collection_addr.clone(),
+                    &send_msg,
+                    &[],
+                )
+                .unwrap();
+            }
+
+            (app, contract_addr, collection_addr)
+        }
+
+        fn list_nfts(app: &mut App, contract_addr: Addr, collection_addr: Addr) -> Vec<String> {
+            let response: NftsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr,
+                    &QueryMsg::ListNfts {
+                        collection: collection_addr.to_string(),
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            response.nfts.into_iter().map(|nft| nft.token_id).collect()
+        }
+
+        fn start_nft_withdraw(
+            app: &mut App,
+            sender: &str,
+            contract_addr: Addr,
+            collection_addr: Addr,
+            token_ids: Vec<&str>,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::StartNftWithdraw {
+                collection: collection_addr.to_string(),
+                token_ids: token_ids.into_iter().map(String::from).collect(),
+            };
+            app.execute_contract(Addr::unchecked(sender), contract_addr, &msg, &[])
+        }
+
+        #[test]
+        fn receive_nft_then_withdraw() {
+            let (mut app, contract_addr, collection_addr) = mock_instantiate_with_nfts();
+
+            // custody is recorded against the depositor
+            let response: NftsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr.clone(),
+                    &QueryMsg::ListNfts {
+                        collection: collection_addr.to_string(),
+                        start_after: None,
+                        limit: Some(1),
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                response.nfts,
+                vec![EscrowedNft {
+                    collection: collection_addr.clone(),
+                    token_id: "badge-1".to_string(),
+                    depositor: Addr::unchecked(USER),
+                }]
+            );
+
+            start_nft_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr.clone(),
+                collection_addr.clone(),
+                vec!["howl.juno"],
+            )
+            .unwrap();
+
+            // not yet
+            let msg = ExecuteMsg::ExecuteNftWithdraw {
+                collection: collection_addr.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::WithdrawalNotReady {},
+                err.downcast().unwrap()
+            );
+
+            app.update_block(advance_one_day_one_hour);
+
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let owner: OwnerOfResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    collection_addr.clone(),
+                    &Cw721BaseQueryMsg::OwnerOf {
+                        token_id: "howl.juno".to_string(),
+                        include_expired: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(owner.owner, WITHDRAW_ADDRESS.to_string());
+
+            assert_eq!(
+                list_nfts(&mut app, contract_addr.clone(), collection_addr),
+                vec!["badge-1".to_string()]
+            );
+            assert!(
+                !withdrawal_requested(&mut app, contract_addr)
+                    .unwrap()
+                    .withdrawal_requested
+            );
+        }
+
+        #[test]
+        fn start_nft_withdraw_requires_escrowed_tokens() {
+            let (mut app, contract_addr, collection_addr) = mock_instantiate_with_nfts();
+
+            let err = start_nft_withdraw(
+                &mut app,
+                USER,
+                contract_addr.clone(),
+                collection_addr.clone(),
+                vec!["howl.juno"],
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            let err = start_nft_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr.clone(),
+                collection_addr.clone(),
+                vec![],
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::NoNftsRequested {}, err.downcast().unwrap());
+
+            let err = start_nft_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr,
+                collection_addr,
+                vec!["howl.juno", "not-escrowed"],
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::NftNotEscrowed {
+                    token_id: "not-escrowed".to_string()
+                },
+                err.downcast().unwrap()
+            );
+        }
+
+        #[test]
+        fn nft_withdraw_can_be_overridden() {
+            let (mut app, contract_addr, collection_addr) = mock_instantiate_with_nfts();
+
+            start_nft_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr.clone(),
+                collection_addr.clone(),
+                COLLECTION_TOKEN_IDS.to_vec(),
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            // an NFT withdrawal can't be claimed as if it were a CW20
+            let msg = ExecuteMsg::ExecuteCW20Withdraw {
+                address: collection_addr.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::WithdrawalCW20Mismatch {},
+                err.downcast().unwrap()
+            );
+
+            exec_override(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap();
+
+            let msg = ExecuteMsg::ExecuteNftWithdraw {
+                collection: collection_addr.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::WithdrawalNotRequested {},
+                err.downcast().unwrap()
+            );
+
+            // and they're all still held
+            assert_eq!(
+                list_nfts(&mut app, contract_addr, collection_addr),
+                vec!["badge-1".to_string(), "howl.juno".to_string()]
+            );
+        }
+
+        #[test]
+        fn proposals_moving_nfts_wait_and_release_them() {
+            let (mut app, contract_addr, collection_addr) = mock_instantiate_with_nfts();
+
+            // with a cold vault, anything moving escrowed assets waits it out
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetVault {
+                    name: "cold".to_string(),
+                    delay_in_days: 5,
+                    limits: vec![],
+                },
+            );
+
+            let transfer: CosmosMsg = WasmMsg::Execute {
+                contract_addr: collection_addr.to_string(),
+                msg: to_binary(&Cw721BaseExecuteMsg::<Extension>::TransferNft {
+                    recipient: USER.to_string(),
+                    token_id: COLLECTION_TOKEN_IDS[0].to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into();
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::ProposeExecution {
+                    msgs: vec![transfer],
+                },
+                &[],
+            )
+            .unwrap();
+            let proposal: Proposal = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetProposal { id: 1 })
+                .unwrap();
+            assert_eq!(proposal.delay_in_days, 5);
+
+            for _ in 0..5 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::ExecuteProposal { id: 1 },
+                &[],
+            )
+            .unwrap();
+
+            // and it's no longer counted as escrowed
+            assert_eq!(
+                list_nfts(&mut app, contract_addr, collection_addr),
+                vec![COLLECTION_TOKEN_IDS[1].to_string()]
+            );
+        }
+    }
+
+    mod staking {
+        use super::*;
+
+        use cosmwasm_std::testing::{
+            mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+            MOCK_CONTRACT_ADDR,
+        };
+        use cosmwasm_std::{
+            coin, from_binary, Attribute, CosmosMsg, Decimal, DistributionMsg, FullDelegation,
+            OwnedDeps, Response, StakingMsg, Validator,
+        };
+
+        const VALIDATOR: &str = "junovaloper-one";
+        const OTHER_VALIDATOR: &str = "junovaloper-two";
+
+        fn validator(address: &str) -> Validator {
+            Validator {
+                address: address.to_string(),
+                commission: Decimal::percent(5),
+                max_commission: Decimal::percent(10),
+                max_change_rate: Decimal::percent(1),
+            }
+        }
+
+        fn full_delegation() -> FullDelegation {
+            FullDelegation {
+                delegator: Addr::unchecked(MOCK_CONTRACT_ADDR),
+                validator: VALIDATOR.to_string(),
+                amount: coin(1_000_000, NATIVE_DENOM),
+                can_redelegate: coin(1_000_000, NATIVE_DENOM),
+                accumulated_rewards: coins(1_234, NATIVE_DENOM),
+            }
+        }
+
+        // cw-multi-test can't stake, so these go straight at the contract
+        fn mock_staking_deps(msg: InstantiateMsg) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+            let mut deps = mock_dependencies();
+            deps.querier.update_staking(
+                NATIVE_DENOM,
+                &[validator(VALIDATOR), validator(OTHER_VALIDATOR)],
+                &[full_delegation()],
+            );
+            crate::contract::instantiate(deps.as_mut(), mock_env(), mock_info(USER, &[]), msg)
+                .unwrap();
+            deps
+        }
+
+        fn exec(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            sender: &str,
+            msg: ExecuteMsg,
+        ) -> Result<Vec<CosmosMsg>, ContractError> {
+            let response =
+                crate::contract::execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg)?;
+            Ok(response.messages.into_iter().map(|sub| sub.msg).collect())
+        }
+
+        fn start_withdraw(
+            deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>,
+            amount: u128,
+        ) -> Result<Response, ContractError> {
+            let msg = ExecuteMsg::StartWithdraw {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(amount),
+                auto_undelegate: true,
+                use_reserve_delay: false,
+                vault: None,
+                category: None,
+            };
+            crate::contract::execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(WITHDRAW_ADDRESS, &[]),
+                msg,
+            )
+        }
+
+        #[test]
+        fn delegate_undelegate_redelegate() {
+            let mut deps = mock_staking_deps(mock_instantiate_msg(1, true, true));
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(500_000, NATIVE_DENOM));
+
+            let msgs = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(500_000),
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                msgs,
+                vec![CosmosMsg::Staking(StakingMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: coin(500_000, NATIVE_DENOM),
+                })]
+            );
+
+            let msgs = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Redelegate {
+                    src_validator: VALIDATOR.to_string(),
+                    dst_validator: OTHER_VALIDATOR.to_string(),
+                    amount: Uint128::new(200_000),
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                msgs,
+                vec![CosmosMsg::Staking(StakingMsg::Redelegate {
+                    src_validator: VALIDATOR.to_string(),
+                    dst_validator: OTHER_VALIDATOR.to_string(),
+                    amount: coin(200_000, NATIVE_DENOM),
+                })]
+            );
+
+            let msgs = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Undelegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(100_000),
+                },
+            )
+            .unwrap();
+            assert_eq!(
+                msgs,
+                vec![CosmosMsg::Staking(StakingMsg::Undelegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: coin(100_000, NATIVE_DENOM),
+                })]
+            );
+        }
+
+        #[test]
+        fn keeper_pool_cant_be_delegated() {
+            let mut deps = mock_staking_deps(mock_instantiate_msg(1, true, true));
+            crate::contract::execute(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(USER, &coins(100_000, NATIVE_DENOM)),
+                ExecuteMsg::FundKeeperPool {},
+            )
+            .unwrap();
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(600_000, NATIVE_DENOM));
+
+            // only 500_000 of that is escrowed
+            let err = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(500_001),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InsufficientContractBalance {});
+
+            exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(500_000),
+                },
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn staking_is_restricted() {
+            let mut deps = mock_staking_deps(mock_instantiate_msg(1, true, true));
+
+            // only the withdraw_address can stake
+            let err = exec(
+                &mut deps,
+                OVERRIDE_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(500_000),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::Unauthorized {});
+
+            // and only to a real validator
+            let err = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: "junovaloper-nope".to_string(),
+                    amount: Uint128::new(500_000),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::ValidatorNotFound {
+                    validator: "junovaloper-nope".to_string()
+                }
+            );
+
+            // and only if the staking denom is one we support
+            let msg = InstantiateMsg {
+                native_denoms: vec![],
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let mut deps = mock_staking_deps(msg);
+            let err = exec(
+                &mut deps,
+                WITHDRAW_ADDRESS,
+                ExecuteMsg::Delegate {
+                    validator: VALIDATOR.to_string(),
+                    amount: Uint128::new(500_000),
+                },
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ContractError::UnsupportedDenom {
+                    denom: NATIVE_DENOM.to_string()
+                }
+            );
+        }
+
+        #[test]
+        fn claim_rewards_and_list_delegations() {
+            let mut deps = mock_staking_deps(mock_instantiate_msg(1, true, true));
+
+            let msgs = exec(&mut deps, WITHDRAW_ADDRESS, ExecuteMsg::ClaimRewards {}).unwrap();
+            assert_eq!(
+                msgs,
+                vec![CosmosMsg::Distribution(
+                    DistributionMsg::WithdrawDelegatorReward {
+                        validator: VALIDATOR.to_string(),
+                    }
+                )]
+            );
+
+            let response: DelegationsResponse = from_binary(
+                &crate::contract::query(deps.as_ref(), mock_env(), QueryMsg::ListDelegations {})
+                    .unwrap(),
+            )
+            .unwrap();
+            assert_eq!(response.delegations, vec![full_delegation()]);
+        }
+
+        #[test]
+        fn start_withdraw_undelegates_shortfall() {
+            let msg = InstantiateMsg {
+                unbonding_period_in_days: Some(21),
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let mut deps = mock_staking_deps(msg);
+            deps.querier.update_staking(
+                NATIVE_DENOM,
+                &[validator(VALIDATOR), validator(OTHER_VALIDATOR)],
+                &[
+                    FullDelegation {
+                        validator: OTHER_VALIDATOR.to_string(),
+                        amount: coin(500_000, NATIVE_DENOM),
+                        ..full_delegation()
+                    },
+                    full_delegation(),
+                ],
+            );
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(300_000, NATIVE_DENOM));
+
+            // covered by the liquid balance, so nothing to do
+            let response = start_withdraw(&mut deps, 300_000).unwrap();
+            assert!(response.messages.is_empty());
+
+            // 1.3m short, taken from the biggest delegation first
+            let response = start_withdraw(&mut deps, 1_600_000).unwrap();
+            let msgs: Vec<CosmosMsg> = response.messages.into_iter().map(|sub| sub.msg).collect();
+            assert_eq!(
+                msgs,
+                vec![
+                    CosmosMsg::Staking(StakingMsg::Undelegate {
+                        validator: VALIDATOR.to_string(),
+                        amount: coin(1_000_000, NATIVE_DENOM),
+                    }),
+                    CosmosMsg::Staking(StakingMsg::Undelegate {
+                        validator: OTHER_VALIDATOR.to_string(),
+                        amount: coin(300_000, NATIVE_DENOM),
+                    }),
+                ]
+            );
+            assert!(response.attributes.contains(&Attribute::new(
+                "warning",
+                "unbonding period is longer than the withdraw delay"
+            )));
+
+            // more than is liquid and staked put together
+            let err = start_withdraw(&mut deps, 2_000_000).unwrap_err();
+            assert_eq!(err, ContractError::InsufficientContractBalance {});
+        }
+
+        #[test]
+        fn execute_withdraw_requires_liquid_balance() {
+            let mut deps = mock_staking_deps(mock_instantiate_msg(1, true, true));
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(300_000, NATIVE_DENOM));
+
+            start_withdraw(&mut deps, 1_000_000).unwrap();
+
+            let mut env = mock_env();
+            env.block.time = env.block.time.plus_seconds(2 * 86400);
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+
+            // still unbonding
+            let err = crate::contract::execute(
+                deps.as_mut(),
+                env.clone(),
+                mock_info(WITHDRAW_ADDRESS, &[]),
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(err, ContractError::InsufficientContractBalance {});
+
+            // and now it's unbonded
+            deps.querier
+                .update_balance(MOCK_CONTRACT_ADDR, coins(1_000_000, NATIVE_DENOM));
+            crate::contract::execute(deps.as_mut(), env, mock_info(WITHDRAW_ADDRESS, &[]), msg)
+                .unwrap();
+        }
+    }
+
+    mod proposals {
+        use super::*;
+
+        use cosmwasm_std::{BankMsg, CosmosMsg, WasmMsg};
+
+        fn bank_send_to_user() -> CosmosMsg {
+            BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: coins(1_000_000, NATIVE_DENOM),
+            }
+            .into()
+        }
+
+        fn propose(
+            app: &mut App,
+            contract_addr: Addr,
+            msgs: Vec<CosmosMsg>,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::ProposeExecution { msgs };
+            app.execute_contract(Addr::unchecked(WITHDRAW_ADDRESS), contract_addr, &msg, &[])
+        }
+
+        fn execute_proposal(
+            app: &mut App,
+            contract_addr: Addr,
+            id: u64,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::ExecuteProposal { id };
+            app.execute_contract(Addr::unchecked(WITHDRAW_ADDRESS), contract_addr, &msg, &[])
+        }
+
+        fn get_proposal(app: &mut App, contract_addr: Addr, id: u64) -> Proposal {
+            app.wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetProposal { id })
+                .unwrap()
+        }
+
+        #[test]
+        fn propose_then_execute() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            propose(&mut app, contract_addr.clone(), vec![bank_send_to_user()]).unwrap();
+
+            // not yet
+            let err = execute_proposal(&mut app, contract_addr.clone(), 1).unwrap_err();
+            assert_eq!(ContractError::ProposalNotReady {}, err.downcast().unwrap());
+
+            app.update_block(advance_one_day_one_hour);
+
+            execute_proposal(&mut app, contract_addr.clone(), 1).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                coins(2_000_000, NATIVE_DENOM)
+            );
+            assert_eq!(
+                get_proposal(&mut app, contract_addr.clone(), 1).status,
+                ProposalStatus::Executed
+            );
+
+            // and only once
+            let err = execute_proposal(&mut app, contract_addr, 1).unwrap_err();
+            assert_eq!(
+                ContractError::ProposalNotPending {},
+                err.downcast().unwrap()
+            );
+        }
+
+        #[test]
+        fn override_cancels_proposal() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            let err = propose(&mut app, contract_addr.clone(), vec![]).unwrap_err();
+            assert_eq!(ContractError::EmptyProposal {}, err.downcast().unwrap());
+
+            propose(&mut app, contract_addr.clone(), vec![bank_send_to_user()]).unwrap();
+            propose(&mut app, contract_addr.clone(), vec![bank_send_to_user()]).unwrap();
+
+            let msg = ExecuteMsg::CancelProposal { id: 1 };
+            let err = app
+                .execute_contract(Addr::unchecked(USER), contract_addr.clone(), &msg, &[])
+                .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            let err = execute_proposal(&mut app, contract_addr.clone(), 1).unwrap_err();
+            assert_eq!(
+                ContractError::ProposalNotPending {},
+                err.downcast().unwrap()
+            );
+
+            // every proposal is kept, whatever happened to it
+            let response: ProposalsResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr,
+                    &QueryMsg::ListProposals {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            let statuses: Vec<ProposalStatus> = response
+                .proposals
+                .into_iter()
+                .map(|proposal| proposal.status)
+                .collect();
+            assert_eq!(
+                statuses,
+                vec![ProposalStatus::Cancelled, ProposalStatus::Pending]
+            );
+        }
+
+        #[test]
+        fn allow_list_restricts_executions() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, true, true);
+
+            // proposed while anything goes
+            propose(&mut app, contract_addr.clone(), vec![bank_send_to_user()]).unwrap();
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetExecutionAllowList {
+                    allow_list: Some(ExecutionAllowList {
+                        msg_types: vec![ExecutionMsgType::Wasm],
+                        contracts: vec![cw20_contract_addr.to_string()],
+                    }),
+                },
+            );
+
+            // but not allowed by the time it's executed
+            let err = execute_proposal(&mut app, contract_addr.clone(), 1).unwrap_err();
+            assert_eq!(
+                ContractError::ExecutionNotAllowed {},
+                err.downcast().unwrap()
+            );
+
+            let transfer = |contract_addr: &Addr| -> CosmosMsg {
+                WasmMsg::Execute {
+                    contract_addr: contract_addr.to_string(),
+                    msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                        recipient: USER.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }
+                .into()
+            };
+
+            let err =
+                propose(&mut app, contract_addr.clone(), vec![bank_send_to_user()]).unwrap_err();
+            assert_eq!(
+                ContractError::ExecutionNotAllowed {},
+                err.downcast().unwrap()
+            );
+
+            let err = propose(
+                &mut app,
+                contract_addr.clone(),
+                vec![transfer(&Addr::unchecked("some-other-contract"))],
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::ExecutionNotAllowed {},
+                err.downcast().unwrap()
+            );
+
+            propose(
+                &mut app,
+                contract_addr.clone(),
+                vec![transfer(&cw20_contract_addr)],
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+
+            execute_proposal(&mut app, contract_addr, 2).unwrap();
+            assert_eq!(
+                get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string()),
+                Uint128::new(2_000_000)
+            );
+        }
+
+        #[test]
+        fn proposals_spend_from_the_default_vault() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            // 1_000_000 of the 3_000_000 is set aside for a stream
+            let start = app.block_info().time.plus_seconds(2 * 86400);
+            let msg = ExecuteMsg::CreateStream {
+                recipient: USER.to_string(),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                total: Uint128::new(1_000_000),
+                start,
+                end: start.plus_seconds(1_000_000),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            let send = |amount: u128| -> CosmosMsg {
+                BankMsg::Send {
+                    to_address: USER.to_string(),
+                    amount: coins(amount, NATIVE_DENOM),
+                }
+                .into()
+            };
+            propose(&mut app, contract_addr.clone(), vec![send(3_000_000)]).unwrap();
+            propose(&mut app, contract_addr.clone(), vec![send(2_000_000)]).unwrap();
+            app.update_block(advance_one_day_one_hour);
+
+            let err = execute_proposal(&mut app, contract_addr.clone(), 1).unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientVaultBalance {},
+                err.downcast().unwrap()
+            );
+
+            execute_proposal(&mut app, contract_addr.clone(), 2).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                coins(1_000_000, NATIVE_DENOM)
+            );
+        }
+
+        #[test]
+        fn proposals_debit_the_cw20_ledger() {
+            let msg = InstantiateMsg {
+                enable_cw20_receive: true,
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            let transfer: CosmosMsg = WasmMsg::Execute {
+                contract_addr: cw20_contract_addr.to_string(),
+                msg: to_binary(&cw20::Cw20ExecuteMsg::Transfer {
+                    recipient: USER.to_string(),
+                    amount: Uint128::new(1_000_000),
+                })
+                .unwrap(),
+                funds: vec![],
+            }
+            .into();
+            propose(&mut app, contract_addr.clone(), vec![transfer]).unwrap();
+            app.update_block(advance_one_day_one_hour);
+            execute_proposal(&mut app, contract_addr.clone(), 1).unwrap();
+
+            assert_eq!(
+                get_escrowed_cw20_balance(&mut app, contract_addr, cw20_contract_addr),
+                Uint128::zero()
+            );
+        }
+    }
+
+    mod keepers {
+        use super::*;
+
+        const KEEPER: &str = "keeper-bot";
+
+        fn mock_instantiate_with_keepers() -> (App, Addr) {
+            let msg = InstantiateMsg {
+                permissionless_execution: true,
+                keeper_tip: Some(Coin {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(1_000),
+                }),
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            app.init_modules(|router, _, storage| {
+                router
+                    .bank
+                    .init_balance(
+                        storage,
+                        &Addr::unchecked(KEEPER),
+                        coins(5_000, NATIVE_DENOM),
+                    )
+                    .unwrap();
+            });
+
+            (app, contract_addr)
+        }
+
+        fn exec_native_withdraw(
+            app: &mut App,
+            sender: &str,
+            contract_addr: Addr,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            app.execute_contract(Addr::unchecked(sender), contract_addr, &msg, &[])
+        }
+
+        #[test]
+        fn keepers_need_permissionless_execution() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+
+            let err = exec_native_withdraw(&mut app, KEEPER, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetPermissionlessExecution { enabled: true },
+            );
+
+            // no tip configured, but it still goes through
+            exec_native_withdraw(&mut app, KEEPER, contract_addr).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &Addr::unchecked(WITHDRAW_ADDRESS)),
+                coins(1_000_000, NATIVE_DENOM)
+            );
+        }
+
+        #[test]
+        fn keeper_is_tipped_from_pool() {
+            let (mut app, contract_addr) = mock_instantiate_with_keepers();
+
+            app.execute_contract(
+                Addr::unchecked(KEEPER),
+                contract_addr.clone(),
+                &ExecuteMsg::FundKeeperPool {},
+                &coins(1_500, NATIVE_DENOM),
+            )
+            .unwrap();
+
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+
+            exec_native_withdraw(&mut app, KEEPER, contract_addr.clone()).unwrap();
+
+            // the funds go to the withdraw_address, and the tip to the keeper
+            assert_eq!(
+                get_balance(&mut app, &Addr::unchecked(WITHDRAW_ADDRESS)),
+                coins(1_000_000, NATIVE_DENOM)
+            );
+            assert_eq!(
+                get_balance(&mut app, &Addr::unchecked(KEEPER)),
+                coins(4_500, NATIVE_DENOM)
+            );
+
+            let pool: KeeperPoolResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetKeeperPool {})
+                .unwrap();
+            assert_eq!(pool.pool, coins(500, NATIVE_DENOM));
+
+            // the pool isn't part of what can be withdrawn
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_500),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+
+            let err = exec_native_withdraw(&mut app, KEEPER, contract_addr).unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientContractBalance {},
+                err.downcast().unwrap()
+            );
+        }
+
+        #[test]
+        fn keeper_tip_is_not_counted_as_liquid() {
+            let (mut app, contract_addr) = mock_instantiate_with_keepers();
+
+            app.execute_contract(
+                Addr::unchecked(KEEPER),
+                contract_addr.clone(),
+                &ExecuteMsg::FundKeeperPool {},
+                &coins(1_500, NATIVE_DENOM),
+            )
+            .unwrap();
+
+            // the tip that's about to be paid is still part of the pool
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(3_000_500),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+
+            let err = exec_native_withdraw(&mut app, KEEPER, contract_addr.clone()).unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientContractBalance {},
+                err.downcast().unwrap()
+            );
+
+            // everything but the pool is fine
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(3_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+            exec_native_withdraw(&mut app, KEEPER, contract_addr.clone()).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                coins(500, NATIVE_DENOM)
+            );
+        }
+    }
+
+    mod guardian {
+        use super::*;
+
+        use crate::helpers::guardian_cancellation_digest;
+        use ed25519_zebra::{SigningKey, VerificationKey};
+
+        fn guardian_signing_key() -> SigningKey {
+            SigningKey::from([7u8; 32])
+        }
+
+        fn guardian_key() -> GuardianKey {
+            let public_key: [u8; 32] = VerificationKey::from(&guardian_signing_key()).into();
+            GuardianKey {
+                algorithm: SignatureAlgorithm::Ed25519,
+                public_key: Binary::from(public_key.to_vec()),
+            }
+        }
+
+        fn sign(app: &App, contract_addr: &Addr, withdrawal_id: u64, nonce: u64) -> Binary {
+            let digest = guardian_cancellation_digest(
+                &app.block_info().chain_id,
+                contract_addr,
+                withdrawal_id,
+                nonce,
+            );
+            let signature: [u8; 64] = guardian_signing_key().sign(&digest).into();
+            Binary::from(signature.to_vec())
+        }
+
+        fn relay_cancellation(
+            app: &mut App,
+            contract_addr: Addr,
+            withdrawal_id: u64,
+            nonce: u64,
+            signature: Binary,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::OverrideWithdrawWithSignature {
+                withdrawal_id,
+                nonce,
+                signature,
+            };
+            // anyone can relay it
+            app.execute_contract(Addr::unchecked(USER), contract_addr, &msg, &[])
+        }
+
+        fn start_withdraw(app: &mut App, contract_addr: Addr) -> u64 {
+            start_native_withdraw(
+                app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            let pending: PendingWithdrawalResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetPendingWithdrawal {})
+                .unwrap();
+            pending.withdrawal.unwrap().id
+        }
+
+        #[test]
+        fn signed_cancellation() {
+            let msg = InstantiateMsg {
+                guardian_key: Some(guardian_key()),
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            let withdrawal_id = start_withdraw(&mut app, contract_addr.clone());
+            assert_eq!(withdrawal_id, 1);
+
+            let signature = sign(&app, &contract_addr, withdrawal_id, 1);
+            relay_cancellation(&mut app, contract_addr.clone(), withdrawal_id, 1, signature)
+                .unwrap();
+            assert!(
+                !withdrawal_requested(&mut app, contract_addr.clone())
+                    .unwrap()
+                    .withdrawal_requested
+            );
+
+            let withdrawal_id = start_withdraw(&mut app, contract_addr.clone());
+            assert_eq!(withdrawal_id, 2);
+
+            // an old signature can't be replayed against a new withdrawal
+            let signature = sign(&app, &contract_addr, 1, 1);
+            let err =
+                relay_cancellation(&mut app, contract_addr.clone(), 1, 1, signature).unwrap_err();
+            assert_eq!(
+                ContractError::WithdrawalIdMismatch {},
+                err.downcast().unwrap()
+            );
+
+            // nor can a nonce be reused
+            let signature = sign(&app, &contract_addr, withdrawal_id, 1);
+            let err = relay_cancellation(
+                &mut app,
+                contract_addr.clone(),
+                withdrawal_id,
+                1,
+                signature.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::NonceAlreadyUsed {}, err.downcast().unwrap());
+
+            // and the signature has to cover the nonce
+            let err =
+                relay_cancellation(&mut app, contract_addr.clone(), withdrawal_id, 2, signature)
+                    .unwrap_err();
+            assert_eq!(ContractError::InvalidSignature {}, err.downcast().unwrap());
+
+            let signature = sign(&app, &contract_addr, withdrawal_id, 2);
+            relay_cancellation(&mut app, contract_addr.clone(), withdrawal_id, 2, signature)
+                .unwrap();
+
+            let status: SecurityStatusResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetSecurityStatus {})
+                .unwrap();
+            assert_eq!(status.last_guardian_nonce, Some(2));
+        }
+
+        #[test]
+        fn guardian_key_must_be_configured() {
+            let msg = InstantiateMsg {
+                guardian_key: Some(GuardianKey {
+                    algorithm: SignatureAlgorithm::Secp256k1,
+                    ..guardian_key()
+                }),
+                ..mock_instantiate_msg(1, true, true)
+            };
+            let mut app = mock_app();
+            let escrow_contract_id = app.store_code(escrow_contract_template());
+            let err = app
+                .instantiate_contract(
+                    escrow_contract_id,
+                    Addr::unchecked(USER),
+                    &msg,
+                    &[],
+                    "dao-escrow",
+                    None,
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::InvalidGuardianKey {},
+                err.downcast().unwrap()
+            );
+
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+            let withdrawal_id = start_withdraw(&mut app, contract_addr.clone());
+            let signature = sign(&app, &contract_addr, withdrawal_id, 1);
+            let err = relay_cancellation(&mut app, contract_addr, withdrawal_id, 1, signature)
+                .unwrap_err();
+            assert_eq!(
+                ContractError::GuardianKeyNotConfigured {},
+                err.downcast().unwrap()
+            );
+        }
+    }
+
+    mod locks {
+        use super::*;
+
+        use crate::state::LockableField;
+        use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+        use cosmwasm_std::Storage;
+
+        fn lock_field(
+            app: &mut App,
+            address: &str,
+            contract_address: Addr,
+            field: LockableField,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::LockField { field };
+            app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
+        }
+
+        #[test]
+        fn lock_field_is_one_way() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            // randos can't lock anything
+            let err = lock_field(
+                &mut app,
+                USER,
+                contract_addr.clone(),
+                LockableField::WithdrawAddress,
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            // still changeable before the lock
+            exec_update_withdraw_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                "new-withdraw-address".to_string(),
+            )
+            .unwrap();
+
+            // the withdraw_address can't lock its own address
+            // that would stop the override replacing it
+            let err = lock_field(
+                &mut app,
+                "new-withdraw-address",
+                contract_addr.clone(),
+                LockableField::WithdrawAddress,
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            // but the override_address can
+            lock_field(
+                &mut app,
+                OVERRIDE_ADDRESS,
+                contract_addr.clone(),
+                LockableField::WithdrawAddress,
+            )
+            .unwrap();
+
+            // and locking again is a no-op
+            lock_field(
+                &mut app,
+                OVERRIDE_ADDRESS,
+                contract_addr.clone(),
+                LockableField::WithdrawAddress,
+            )
+            .unwrap();
+
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(config.locked_fields, vec![LockableField::WithdrawAddress]);
+
+            let err = exec_update_withdraw_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                WITHDRAW_ADDRESS.to_string(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::FieldLocked {
+                    field: LockableField::WithdrawAddress
+                },
+                err.downcast().unwrap()
+            );
+
+            // the override address is untouched
+            exec_update_override_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr,
+                "new-override-address".to_string(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn locked_fields_block_config_updates() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, true, true);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetWithdrawDelay { days: 3 },
+            );
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(config.withdraw_delay_in_days, 3);
+
+            // lock the delay, then it can't even be proposed
+            lock_field(
+                &mut app,
+                OVERRIDE_ADDRESS,
+                contract_addr.clone(),
+                LockableField::WithdrawDelay,
+            )
+            .unwrap();
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::SetWithdrawDelay { days: 0 },
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::FieldLocked {
+                    field: LockableField::WithdrawDelay
+                },
+                err.downcast().unwrap()
+            );
+
+            // an update that was already pending can't go through either
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::SetCw20Receive { enabled: true },
+            };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            lock_field(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr.clone(),
+                LockableField::Cw20Receive,
+            )
+            .unwrap();
+            for _ in 0..3 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &ExecuteMsg::ExecuteConfigUpdate {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::FieldLocked {
+                    field: LockableField::Cw20Receive
+                },
+                err.downcast().unwrap()
+            );
+
+            let config = get_config(&mut app, contract_addr).unwrap();
+            assert!(!config.enable_cw20_receive);
+        }
+
+        #[test]
+        fn migrate_turns_immutable_flags_into_locks() {
+            let mut deps = mock_dependencies();
+            crate::contract::instantiate(
+                deps.as_mut(),
+                mock_env(),
+                mock_info(USER, &[]),
+                mock_instantiate_msg(1, false, false),
+            )
+            .unwrap();
+
+            // rewrite the config the way older versions stored it
+            let raw = String::from_utf8(deps.storage.get(b"config").unwrap()).unwrap();
+            assert!(raw.contains(r#""locked_fields":[]"#));
+            let legacy = raw.replace(
+                r#""locked_fields":[]"#,
+                r#""set_withdraw_as_immutable":true,"set_override_as_immutable":false"#,
+            );
+            deps.storage.set(b"config", legacy.as_bytes());
+
+            crate::contract::migrate(
+                deps.as_mut(),
+                mock_env(),
+                MigrateMsg {
+                    cw20_addresses: vec![],
+                    native_denoms: vec![],
+                },
+            )
+            .unwrap();
+
+            let config = crate::state::CONFIG.load(&deps.storage).unwrap();
+            assert_eq!(config.locked_fields, vec![LockableField::WithdrawAddress]);
+        }
+    }
+
+    mod renounce {
+        use super::*;
+
+        use crate::state::LockableField;
+
+        fn renounce(
+            app: &mut App,
+            address: &str,
+            contract_address: Addr,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::RenounceOverride {};
+            app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
+        }
+
+        #[test]
+        fn renounce_override_takes_two_calls() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            // only the override_address can renounce
+            let err = renounce(&mut app, WITHDRAW_ADDRESS, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            renounce(&mut app, OVERRIDE_ADDRESS, contract_addr.clone()).unwrap();
+            let status: SecurityStatusResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetSecurityStatus {})
+                .unwrap();
+            assert!(status.override_renounce_ready_at.is_some());
+
+            // confirming straight away doesn't work
+            let err = renounce(&mut app, OVERRIDE_ADDRESS, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::RenounceNotReady {}, err.downcast().unwrap());
+
+            app.update_block(advance_one_day_one_hour);
+            renounce(&mut app, OVERRIDE_ADDRESS, contract_addr.clone()).unwrap();
+
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(config.override_address, None);
+
+            // the override powers are gone
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+            let err = exec_override(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::OverrideRenounced {}, err.downcast().unwrap());
+
+            let err = exec_update_withdraw_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                OVERRIDE_ADDRESS.to_string(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::OverrideRenounced {}, err.downcast().unwrap());
+
+            // but the time-lock still works
+            app.update_block(advance_one_day_one_hour);
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            let contract_balance = get_balance(&mut app, &contract_addr);
+            assert_eq!(contract_balance, coins(2_000_000, NATIVE_DENOM));
+        }
+
+        #[test]
+        fn renounce_can_be_cancelled_or_locked() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            renounce(&mut app, OVERRIDE_ADDRESS, contract_addr.clone()).unwrap();
+
+            // the withdraw_address can throw it out
+            let msg = ExecuteMsg::CancelRenounceOverride {};
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::RenounceNotRequested {},
+                err.downcast().unwrap()
+            );
+
+            // so after the delay, the next call only starts it again
+            app.update_block(advance_one_day_one_hour);
+            renounce(&mut app, OVERRIDE_ADDRESS, contract_addr.clone()).unwrap();
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(
+                config.override_address,
+                Some(Addr::unchecked(OVERRIDE_ADDRESS))
+            );
+
+            // a locked override_address can't be given up
+            let msg = ExecuteMsg::LockField {
+                field: LockableField::OverrideAddress,
+            };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            app.update_block(advance_one_day_one_hour);
+            let err = renounce(&mut app, OVERRIDE_ADDRESS, contract_addr).unwrap_err();
+            assert_eq!(
+                ContractError::FieldLocked {
+                    field: LockableField::OverrideAddress
+                },
+                err.downcast().unwrap()
+            );
+        }
+    }
+
+    mod evacuate {
+        use super::*;
+
+        use crate::msg::EvacuationsResponse;
+        use crate::state::Asset;
+
+        const SAFE_HARBOR_ADDRESS: &str = "safe-harbor-cold-multisig-address";
+
+        fn mock_instantiate_with_safe_harbor() -> (App, Addr, Addr) {
+            let msg = InstantiateMsg {
+                safe_harbor_address: Some(SAFE_HARBOR_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+            (app, contract_addr, cw20_contract_addr)
+        }
+
+        fn evacuate(
+            app: &mut App,
+            address: &str,
+            contract_address: Addr,
+            assets: Vec<Asset>,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::Evacuate { assets };
+            app.execute_contract(Addr::unchecked(address), contract_address, &msg, &[])
+        }
+
+        #[test]
+        fn evacuate_moves_assets_to_safe_harbor() {
+            let (mut app, contract_addr, cw20_contract_addr) = mock_instantiate_with_safe_harbor();
+            let assets = vec![
+                Asset::Native {
+                    denom: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(1_000_000),
+                },
+                Asset::Cw20 {
+                    address: cw20_contract_addr.to_string(),
+                    amount: Uint128::new(2_000_000),
+                },
+            ];
+
+            // the withdraw_address can't do this
+            let err = evacuate(
+                &mut app,
+                WITHDRAW_ADDRESS,
+                contract_addr.clone(),
+                assets.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            // but it can still have a withdrawal pending
+            start_native_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(2_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+
+            // the override_address can, with no delay
+            evacuate(
+                &mut app,
+                OVERRIDE_ADDRESS,
+                contract_addr.clone(),
+                assets.clone(),
+            )
+            .unwrap();
+
+            let safe_harbor_balance = get_balance(&mut app, &Addr::unchecked(SAFE_HARBOR_ADDRESS));
+            assert_eq!(safe_harbor_balance, coins(1_000_000, NATIVE_DENOM));
+            let safe_harbor_cw20_balance = get_cw20_balance(
+                &mut app,
+                cw20_contract_addr.clone(),
+                SAFE_HARBOR_ADDRESS.to_string(),
+            );
+            assert_eq!(safe_harbor_cw20_balance, Uint128::new(2_000_000));
+            let override_balance = get_balance(&mut app, &Addr::unchecked(OVERRIDE_ADDRESS));
+            assert!(override_balance.is_empty());
+
+            // which throws out the pending withdrawal
+            let pending: PendingWithdrawalResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetPendingWithdrawal {})
+                .unwrap();
+            assert!(pending.withdrawal.is_none());
+
+            // and it's on the record
+            let msg = QueryMsg::ListEvacuations {
+                start_after: None,
+                limit: None,
+            };
+            let result: EvacuationsResponse =
+                app.wrap().query_wasm_smart(contract_addr, &msg).unwrap();
+            assert_eq!(result.evacuations.len(), 1);
+            let evacuation = &result.evacuations[0];
+            assert_eq!(evacuation.id, 1);
+            assert_eq!(evacuation.evacuated_by, Addr::unchecked(OVERRIDE_ADDRESS));
+            assert_eq!(
+                evacuation.safe_harbor_address,
+                Addr::unchecked(SAFE_HARBOR_ADDRESS)
+            );
+            assert_eq!(evacuation.assets, assets);
+        }
+
+        #[test]
+        fn evacuate_needs_a_safe_harbor() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let assets = vec![Asset::Native {
+                denom: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(1_000_000),
+            }];
+            let err =
+                evacuate(&mut app, OVERRIDE_ADDRESS, contract_addr, assets.clone()).unwrap_err();
+            assert_eq!(
+                ContractError::SafeHarborNotConfigured {},
+                err.downcast().unwrap()
+            );
+
+            // the override_address can't be its own safe harbor
+            let mut app = mock_app();
+            let escrow_contract_id = app.store_code(escrow_contract_template());
+            let msg = InstantiateMsg {
+                safe_harbor_address: Some(OVERRIDE_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let err = app
+                .instantiate_contract(
+                    escrow_contract_id,
+                    Addr::unchecked(USER),
+                    &msg,
+                    &[],
+                    "dao-escrow",
+                    None,
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidSafeHarbor {}, err.downcast().unwrap());
+
+            // nor become it later
+            let (mut app, contract_addr, _) = mock_instantiate_with_safe_harbor();
+            let err = exec_update_override_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                SAFE_HARBOR_ADDRESS.to_string(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::InvalidSafeHarbor {}, err.downcast().unwrap());
+
+            // and it can't move more than is escrowed
+            let assets = vec![Asset::Native {
+                denom: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(3_000_001),
+            }];
+            let err = evacuate(&mut app, OVERRIDE_ADDRESS, contract_addr, assets).unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientContractBalance {},
+                err.downcast().unwrap()
+            );
+        }
+    }
+
+    mod reserve {
+        use super::*;
+
+        use crate::msg::WithdrawableResponse;
+        use crate::state::{Proposal, ReserveFloor};
+        use cosmwasm_std::{BankMsg, CosmosMsg};
+
+        const RECOVERY_ADDRESS: &str = "recovery-cold-multisig-address";
+
+        fn start_withdraw(
+            app: &mut App,
+            contract_address: Addr,
+            denom_or_address: String,
+            amount: u128,
+            use_reserve_delay: bool,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::StartWithdraw {
+                denom_or_address,
+                amount: Uint128::new(amount),
+                auto_undelegate: false,
+                use_reserve_delay,
+                vault: None,
+                category: None,
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+        }
+
+        fn get_withdrawable(
+            app: &mut App,
+            contract_address: Addr,
+            denom_or_address: String,
+        ) -> WithdrawableResponse {
+            let msg = QueryMsg::GetWithdrawable {
+                denom_or_address,
+                vault: None,
+            };
+            app.wrap().query_wasm_smart(contract_address, &msg).unwrap()
+        }
+
+        #[test]
+        fn reserve_floor_needs_the_reserve_delay() {
+            let msg = InstantiateMsg {
+                reserve_floors: vec![ReserveFloor {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(2_000_000),
+                }],
+                reserve_delay_in_days: Some(5),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            let withdrawable =
+                get_withdrawable(&mut app, contract_addr.clone(), NATIVE_DENOM.to_string());
+            assert_eq!(withdrawable.balance, Uint128::new(3_000_000));
+            assert_eq!(withdrawable.reserve_floor, Uint128::new(2_000_000));
+            assert_eq!(withdrawable.withdrawable, Uint128::new(1_000_000));
+
+            // can't dip into the reserve on the normal delay
+            let err = start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                NATIVE_DENOM.to_string(),
+                1_500_000,
+                false,
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(2_000_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            // but anything above it is fine
+            start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                NATIVE_DENOM.to_string(),
+                1_000_000,
+                false,
+            )
+            .unwrap();
+
+            // opting in to the reserve delay takes much longer
+            start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                NATIVE_DENOM.to_string(),
+                1_500_000,
+                true,
+            )
+            .unwrap();
+            let pending: PendingWithdrawalResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetPendingWithdrawal {})
+                .unwrap();
+            assert_eq!(
+                pending.withdrawal.unwrap().ready_at,
+                app.block_info().time.plus_seconds(5 * 86400)
+            );
+        }
+
+        #[test]
+        fn reserve_floors_are_governed() {
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate(1, false, false);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: cw20_contract_addr.to_string(),
+                    amount: Uint128::new(4_000_000),
+                },
+            );
+            let withdrawable = get_withdrawable(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.to_string(),
+            );
+            assert_eq!(withdrawable.withdrawable, Uint128::new(1_000_000));
+
+            let err = start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.to_string(),
+                2_000_000,
+                false,
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(4_000_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            // there's no reserve delay, so the reserve can't be touched
+            let err = start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                cw20_contract_addr.to_string(),
+                2_000_000,
+                true,
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::ReserveDelayNotConfigured {},
+                err.downcast().unwrap()
+            );
+
+            // and it has to be longer than the withdraw delay
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::SetReserveDelay { days: Some(1) },
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::InvalidReserveDelay {},
+                err.downcast().unwrap()
+            );
+
+            // a zero floor removes it
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: cw20_contract_addr.to_string(),
+                    amount: Uint128::zero(),
+                },
+            );
+            let config = get_config(&mut app, contract_addr.clone()).unwrap();
+            assert!(config.reserve_floors.is_empty());
+            start_withdraw(
+                &mut app,
+                contract_addr,
+                cw20_contract_addr.to_string(),
+                2_000_000,
+                false,
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn reserve_floor_holds_on_every_path() {
+            let msg = InstantiateMsg {
+                reserve_floors: vec![ReserveFloor {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(2_000_000),
+                }],
+                reserve_delay_in_days: Some(5),
+                recovery_address: Some(RECOVERY_ADDRESS.to_string()),
+                inactivity_period_in_days: Some(2),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+
+            start_withdraw(
+                &mut app,
+                contract_addr.clone(),
+                NATIVE_DENOM.to_string(),
+                1_000_000,
+                false,
+            )
+            .unwrap();
+
+            // the floor goes up while the withdrawal waits
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(2_500_000),
+                },
+            );
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(2_500_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            // recovery withdrawals are held to it too
+            app.update_block(advance_one_day_one_hour);
+            app.update_block(advance_one_day_one_hour);
+            let err = start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(1_000_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(2_500_000)
+                },
+                err.downcast().unwrap()
+            );
+            start_recovery_withdraw(
+                &mut app,
+                RECOVERY_ADDRESS.to_string(),
+                contract_addr,
+                Uint128::new(500_000),
+                NATIVE_DENOM.to_string(),
+            )
+            .unwrap();
+        }
+
+        #[test]
+        fn proposals_moving_funds_need_the_reserve_delay() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            let propose = |app: &mut App, msgs: Vec<CosmosMsg>| {
+                let msg = ExecuteMsg::ProposeExecution { msgs };
+                app.execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+            };
+            let execute_proposal = |app: &mut App, id: u64| {
+                let msg = ExecuteMsg::ExecuteProposal { id };
+                app.execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+            };
+            let bank_send: CosmosMsg = BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: coins(2_000_000, NATIVE_DENOM),
+            }
+            .into();
+
+            // proposed before there was a floor
+            propose(&mut app, vec![bank_send.clone()]).unwrap();
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(2_000_000),
+                },
+            );
+
+            // there's no reserve delay, so funds can't be moved by proposal at all
+            let err = propose(&mut app, vec![bank_send.clone()]).unwrap_err();
+            assert_eq!(
+                ContractError::ReserveDelayNotConfigured {},
+                err.downcast().unwrap()
+            );
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveDelay { days: Some(5) },
+            );
+
+            // the old proposal only waited out the withdraw delay
+            let err = execute_proposal(&mut app, 1).unwrap_err();
+            assert_eq!(
+                ContractError::ProposalDelayTooShort {},
+                err.downcast().unwrap()
+            );
+
+            propose(&mut app, vec![bank_send]).unwrap();
+            let proposal: Proposal = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetProposal { id: 2 })
+                .unwrap();
+            assert_eq!(proposal.delay_in_days, 5);
+
+            for _ in 0..4 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            let err = execute_proposal(&mut app, 2).unwrap_err();
+            assert_eq!(ContractError::ProposalNotReady {}, err.downcast().unwrap());
+
+            app.update_block(advance_one_day_one_hour);
+            execute_proposal(&mut app, 2).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                coins(1_000_000, NATIVE_DENOM)
+            );
+        }
+    }
+
+    mod vaults {
+        use super::*;
+
+        use crate::msg::{VaultTransfersResponse, WithdrawableResponse};
+        use crate::state::{Asset, Proposal, VaultLimit};
+        use cosmwasm_std::{to_binary, BankMsg, CosmosMsg, WasmMsg};
+
+        const COLD_VAULT: &str = "cold";
+        const WARM_VAULT: &str = "warm";
+        const SAFE_HARBOR_ADDRESS: &str = "safe-harbor-cold-multisig-address";
+
+        fn set_cold_vault(app: &mut App, contract_address: Addr, limits: Vec<VaultLimit>) {
+            exec_config_update(
+                app,
+                contract_address,
+                ConfigUpdate::SetVault {
+                    name: COLD_VAULT.to_string(),
+                    delay_in_days: 5,
+                    limits,
+                },
+            );
+        }
+
+        fn start_vault_transfer(
+            app: &mut App,
+            contract_address: Addr,
+            from: Option<&str>,
+            to: Option<&str>,
+            amount: u128,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::StartVaultTransfer {
+                from: from.map(|name| name.to_string()),
+                to: to.map(|name| name.to_string()),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(amount),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+        }
+
+        fn start_vault_withdraw(
+            app: &mut App,
+            contract_address: Addr,
+            vault: Option<&str>,
+            amount: u128,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::StartWithdraw {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(amount),
+                auto_undelegate: false,
+                use_reserve_delay: false,
+                vault: vault.map(|name| name.to_string()),
+                category: None,
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+        }
+
+        fn get_withdrawable(app: &mut App, contract_address: Addr, vault: Option<&str>) -> Uint128 {
+            let msg = QueryMsg::GetWithdrawable {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                vault: vault.map(|name| name.to_string()),
+            };
+            let result: WithdrawableResponse =
+                app.wrap().query_wasm_smart(contract_address, &msg).unwrap();
+            result.withdrawable
+        }
+
+        #[test]
+        fn only_vaults_that_are_set_up_hold_funds() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            let err = start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(COLD_VAULT),
+                1_000_000,
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::VaultNotFound {
+                    name: COLD_VAULT.to_string()
+                },
+                err.downcast().unwrap()
+            );
+
+            set_cold_vault(&mut app, contract_addr.clone(), vec![]);
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(COLD_VAULT),
+                1_000_000,
+            )
+            .unwrap();
+
+            // it can't be removed while it holds anything
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::RemoveVault {
+                    name: COLD_VAULT.to_string(),
+                },
+            };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            for _ in 0..5 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &ExecuteMsg::ExecuteConfigUpdate {},
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::VaultNotEmpty {}, err.downcast().unwrap());
+
+            // once it's emptied, it can go
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                Some(COLD_VAULT),
+                None,
+                1_000_000,
+            )
+            .unwrap();
+            for _ in 0..5 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::ExecuteVaultTransfer { id: 1 },
+                &[],
+            )
+            .unwrap();
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &ExecuteMsg::ExecuteConfigUpdate {},
+                &[],
+            )
+            .unwrap();
+
+            app.wrap()
+                .query_wasm_smart::<VaultBalanceResponse>(
+                    contract_addr,
+                    &QueryMsg::GetVaultBalance {
+                        vault: Some(COLD_VAULT.to_string()),
+                        denom_or_address: NATIVE_DENOM.to_string(),
+                    },
+                )
+                .unwrap_err();
+        }
+
+        #[test]
+        fn vaults_have_their_own_delays() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            // a vault can't be made too cold to ever change
+            let msg = ExecuteMsg::StartConfigUpdate {
+                update: ConfigUpdate::SetVault {
+                    name: COLD_VAULT.to_string(),
+                    delay_in_days: u64::MAX / 2,
+                    limits: vec![],
+                },
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::InvalidDelay {}, err.downcast().unwrap());
+
+            set_cold_vault(&mut app, contract_addr.clone(), vec![]);
+
+            // a withdrawal from the default vault is started
+            start_vault_withdraw(&mut app, contract_addr.clone(), None, 2_000_000).unwrap();
+
+            // but moving into a colder vault is instant
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(COLD_VAULT),
+                2_000_000,
+            )
+            .unwrap();
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone(), None),
+                Uint128::new(1_000_000)
+            );
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone(), Some(COLD_VAULT)),
+                Uint128::new(2_000_000)
+            );
+
+            // so the pending withdrawal can't take it any more
+            app.update_block(advance_one_day_one_hour);
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientVaultBalance {},
+                err.downcast().unwrap()
+            );
+
+            // a withdrawal from the cold vault uses its delay
+            start_vault_withdraw(&mut app, contract_addr.clone(), Some(COLD_VAULT), 2_000_000)
+                .unwrap();
+            let pending: PendingWithdrawalResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetPendingWithdrawal {})
+                .unwrap();
+            let withdrawal = pending.withdrawal.unwrap();
+            assert_eq!(withdrawal.vault, Some(COLD_VAULT.to_string()));
+            assert_eq!(
+                withdrawal.ready_at,
+                app.block_info().time.plus_seconds(5 * 86400)
+            );
+
+            for _ in 0..5 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr, Some(COLD_VAULT)),
+                Uint128::zero()
+            );
+        }
+
+        #[test]
+        fn moving_out_of_a_colder_vault_is_delayed() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            set_cold_vault(
+                &mut app,
+                contract_addr.clone(),
+                vec![VaultLimit {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(500_000),
+                }],
+            );
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(COLD_VAULT),
+                2_000_000,
+            )
+            .unwrap();
+
+            // withdrawals from the cold vault are limited
+            let err =
+                start_vault_withdraw(&mut app, contract_addr.clone(), Some(COLD_VAULT), 600_000)
+                    .unwrap_err();
+            assert_eq!(
+                ContractError::VaultLimitExceeded {
+                    limit: Uint128::new(500_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            // moving back out waits out the cold delay
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                Some(COLD_VAULT),
+                None,
+                1_500_000,
+            )
+            .unwrap();
+            let msg = QueryMsg::ListVaultTransfers {
+                start_after: None,
+                limit: None,
+            };
+            let result: VaultTransfersResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(result.transfers.len(), 1);
+            let id = result.transfers[0].id;
+
+            // and it isn't in either vault in the meantime
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone(), Some(COLD_VAULT)),
+                Uint128::new(500_000)
+            );
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone(), None),
+                Uint128::new(1_000_000)
+            );
+
+            let msg = ExecuteMsg::ExecuteVaultTransfer { id };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::VaultTransferNotReady {},
+                err.downcast().unwrap()
+            );
+
+            for _ in 0..5 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone(), None),
+                Uint128::new(2_500_000)
+            );
+
+            // the override_address can throw out a move
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                Some(COLD_VAULT),
+                None,
+                500_000,
+            )
+            .unwrap();
+            let msg = ExecuteMsg::CancelVaultTransfer { id: id + 1 };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr, Some(COLD_VAULT)),
+                Uint128::new(500_000)
+            );
+        }
+
+        #[test]
+        fn proposals_moving_funds_wait_out_the_coldest_vault() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            set_cold_vault(&mut app, contract_addr.clone(), vec![]);
+
+            let send: CosmosMsg = BankMsg::Send {
+                to_address: USER.to_string(),
+                amount: coins(1_000_000, NATIVE_DENOM),
+            }
+            .into();
+            let heartbeat: CosmosMsg = WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&ExecuteMsg::Heartbeat {}).unwrap(),
+                funds: vec![],
+            }
+            .into();
+            for msgs in [vec![send], vec![heartbeat]] {
+                let msg = ExecuteMsg::ProposeExecution { msgs };
+                app.execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+            }
+
+            // sending funds could be spending the cold vault
+            let proposal: Proposal = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &QueryMsg::GetProposal { id: 1 })
+                .unwrap();
+            assert_eq!(proposal.delay_in_days, 5);
+
+            // but anything else just waits out the withdraw delay
+            let proposal: Proposal = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetProposal { id: 2 })
+                .unwrap();
+            assert_eq!(proposal.delay_in_days, 1);
+        }
+
+        #[test]
+        fn evacuating_takes_from_the_hottest_vault_first() {
+            let msg = InstantiateMsg {
+                safe_harbor_address: Some(SAFE_HARBOR_ADDRESS.to_string()),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate_with_msg(msg);
+            set_cold_vault(&mut app, contract_addr.clone(), vec![]);
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetVault {
+                    name: WARM_VAULT.to_string(),
+                    delay_in_days: 2,
+                    limits: vec![],
+                },
+            );
+
+            // 500_000 in the default vault, 500_000 warm and 2_000_000 cold,
+            // with 500_000 of that on its way out
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(WARM_VAULT),
+                500_000,
+            )
+            .unwrap();
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                None,
+                Some(COLD_VAULT),
+                2_000_000,
+            )
+            .unwrap();
+            start_vault_transfer(
+                &mut app,
+                contract_addr.clone(),
+                Some(COLD_VAULT),
+                None,
+                500_000,
+            )
+            .unwrap();
+
+            let evacuate = |app: &mut App, amounts: &[u128]| {
+                let msg = ExecuteMsg::Evacuate {
+                    assets: amounts
+                        .iter()
+                        .map(|amount| Asset::Native {
+                            denom: NATIVE_DENOM.to_string(),
+                            amount: Uint128::new(*amount),
+                        })
+                        .collect(),
+                };
+                app.execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap();
+            };
+            let vault_balances = |app: &mut App| -> Vec<Uint128> {
+                [None, Some(WARM_VAULT), Some(COLD_VAULT)]
+                    .iter()
+                    .map(|vault| get_withdrawable(app, contract_addr.clone(), *vault))
+                    .collect()
+            };
+            let transfers = |app: &mut App| -> usize {
+                let msg = QueryMsg::ListVaultTransfers {
+                    start_after: None,
+                    limit: None,
+                };
+                let result: VaultTransfersResponse = app
+                    .wrap()
+                    .query_wasm_smart(contract_addr.clone(), &msg)
+                    .unwrap();
+                result.transfers.len()
+            };
+
+            // a little comes out of the default vault alone
+            evacuate(&mut app, &[300_000]);
+            assert_eq!(
+                vault_balances(&mut app),
+                vec![
+                    Uint128::new(200_000),
+                    Uint128::new(500_000),
+                    Uint128::new(1_500_000)
+                ]
+            );
+            assert_eq!(transfers(&mut app), 1);
+
+            // then the warm vault, before the cold one
+            // even when the asset is listed more than once
+            evacuate(&mut app, &[300_000, 300_000]);
+            assert_eq!(
+                vault_balances(&mut app),
+                vec![
+                    Uint128::zero(),
+                    Uint128::new(100_000),
+                    Uint128::new(1_500_000)
+                ]
+            );
+            assert_eq!(transfers(&mut app), 1);
+
+            // the cold vault can't cover its pending transfer any more, so that goes
+            evacuate(&mut app, &[1_800_000]);
+            assert_eq!(
+                vault_balances(&mut app),
+                vec![Uint128::zero(), Uint128::zero(), Uint128::new(300_000)]
+            );
+            assert_eq!(transfers(&mut app), 0);
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                coins(300_000, NATIVE_DENOM)
+            );
+        }
+    }
+
+    mod budgets {
+        use super::*;
+
+        use crate::msg::BudgetResponse;
+        use crate::state::BudgetAllocation;
+
+        const OPS: &str = "ops";
+
+        fn set_ops_budget(app: &mut App, contract_address: Addr, amount: u128) {
+            exec_config_update(
+                app,
+                contract_address,
+                ConfigUpdate::SetBudgetCategory {
+                    name: OPS.to_string(),
+                    period_in_days: 30,
+                    allocations: vec![BudgetAllocation {
+                        denom_or_address: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(amount),
+                    }],
+                },
+            );
+        }
+
+        fn start_category_withdraw(
+            app: &mut App,
+            contract_address: Addr,
+            category: &str,
+            amount: u128,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::StartWithdraw {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(amount),
+                auto_undelegate: false,
+                use_reserve_delay: false,
+                vault: None,
+                category: Some(category.to_string()),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+        }
+
+        fn claim_native_withdraw(app: &mut App, contract_address: Addr) {
+            app.update_block(advance_one_day_one_hour);
+            let msg = ExecuteMsg::ExecuteNativeWithdraw {
+                denom: NATIVE_DENOM.to_string(),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+            .unwrap();
+        }
+
+        fn get_budget(app: &mut App, contract_address: Addr) -> BudgetResponse {
+            let msg = QueryMsg::GetBudget {
+                category: OPS.to_string(),
+            };
+            app.wrap().query_wasm_smart(contract_address, &msg).unwrap()
+        }
+
+        #[test]
+        fn withdrawals_are_checked_against_the_budget() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+
+            // a period has to be between a day and about ten years
+            for period_in_days in [0, 3651, u64::MAX] {
+                let msg = ExecuteMsg::StartConfigUpdate {
+                    update: ConfigUpdate::SetBudgetCategory {
+                        name: OPS.to_string(),
+                        period_in_days,
+                        allocations: vec![],
+                    },
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(OVERRIDE_ADDRESS),
+                        contract_addr.clone(),
+                        &msg,
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(
+                    ContractError::InvalidBudgetPeriod {},
+                    err.downcast().unwrap()
+                );
+            }
+
+            // there's no such category yet
+            let err =
+                start_category_withdraw(&mut app, contract_addr.clone(), OPS, 100_000).unwrap_err();
+            assert_eq!(
+                ContractError::BudgetCategoryNotFound {
+                    name: OPS.to_string()
+                },
+                err.downcast().unwrap()
+            );
+
+            set_ops_budget(&mut app, contract_addr.clone(), 1_000_000);
+
+            let err = start_category_withdraw(&mut app, contract_addr.clone(), OPS, 1_500_000)
+                .unwrap_err();
+            assert_eq!(
+                ContractError::BudgetExceeded {
+                    remaining: Uint128::new(1_000_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            start_category_withdraw(&mut app, contract_addr.clone(), OPS, 600_000).unwrap();
+            let budget = get_budget(&mut app, contract_addr.clone());
+            assert_eq!(budget.period_in_days, 30);
+            assert_eq!(budget.lines[0].allocated, Uint128::new(1_000_000));
+            assert_eq!(budget.lines[0].spent, Uint128::zero());
+            assert_eq!(budget.lines[0].pending, Uint128::new(600_000));
+            assert_eq!(budget.lines[0].remaining, Uint128::new(400_000));
+
+            // once claimed, it's spent
+            claim_native_withdraw(&mut app, contract_addr.clone());
+            let budget = get_budget(&mut app, contract_addr.clone());
+            assert_eq!(budget.lines[0].spent, Uint128::new(600_000));
+            assert_eq!(budget.lines[0].pending, Uint128::zero());
+            assert_eq!(budget.lines[0].remaining, Uint128::new(400_000));
+
+            let err =
+                start_category_withdraw(&mut app, contract_addr.clone(), OPS, 500_000).unwrap_err();
+            assert_eq!(
+                ContractError::BudgetExceeded {
+                    remaining: Uint128::new(400_000)
+                },
+                err.downcast().unwrap()
+            );
+            start_category_withdraw(&mut app, contract_addr, OPS, 400_000).unwrap();
+        }
+
+        #[test]
+        fn a_new_period_resets_the_budget() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            set_ops_budget(&mut app, contract_addr.clone(), 1_000_000);
+
+            start_category_withdraw(&mut app, contract_addr.clone(), OPS, 1_000_000).unwrap();
+            claim_native_withdraw(&mut app, contract_addr.clone());
+
+            let err = start_category_withdraw(&mut app, contract_addr.clone(), OPS, 1).unwrap_err();
+            assert_eq!(
+                ContractError::BudgetExceeded {
+                    remaining: Uint128::zero()
+                },
+                err.downcast().unwrap()
+            );
+
+            // the next period starts from scratch
+            let budget = get_budget(&mut app, contract_addr.clone());
+            app.update_block(|block| block.time = budget.period_end);
+            let budget = get_budget(&mut app, contract_addr.clone());
+            assert_eq!(budget.lines[0].spent, Uint128::zero());
+            assert_eq!(budget.lines[0].remaining, Uint128::new(1_000_000));
+
+            start_category_withdraw(&mut app, contract_addr, OPS, 1_000_000).unwrap();
+        }
+
+        #[test]
+        fn lengthening_the_period_keeps_the_budget() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            set_ops_budget(&mut app, contract_addr.clone(), 1_000_000);
+            start_category_withdraw(&mut app, contract_addr.clone(), OPS, 1_000_000).unwrap();
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetBudgetCategory {
+                    name: OPS.to_string(),
+                    period_in_days: 60,
+                    allocations: vec![BudgetAllocation {
+                        denom_or_address: NATIVE_DENOM.to_string(),
+                        amount: Uint128::new(1_000_000),
+                    }],
+                },
+            );
+
+            // it was started in a 30 day period, but it's counted against the 60 day one
+            claim_native_withdraw(&mut app, contract_addr.clone());
+            let budget = get_budget(&mut app, contract_addr.clone());
+            assert_eq!(budget.period_in_days, 60);
+            assert_eq!(budget.lines[0].spent, Uint128::new(1_000_000));
+
+            let err = start_category_withdraw(&mut app, contract_addr, OPS, 1).unwrap_err();
+            assert_eq!(
+                ContractError::BudgetExceeded {
+                    remaining: Uint128::zero()
+                },
+                err.downcast().unwrap()
+            );
+        }
+    }
+
+    mod schedules {
+        use super::*;
+
+        use crate::msg::SchedulesResponse;
+
+        fn create_schedule(app: &mut App, contract_address: Addr, count: u64) {
+            let msg = ExecuteMsg::CreateSchedule {
+                recipient: USER.to_string(),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(100_000),
+                interval_in_days: 30,
+                count,
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+            .unwrap();
+        }
+
+        fn claim(app: &mut App, contract_address: Addr) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::ClaimScheduledPayment { id: 1 };
+            app.execute_contract(Addr::unchecked("anyone"), contract_address, &msg, &[])
+        }
+
+        fn user_balance(app: &mut App) -> Uint128 {
+            app.wrap().query_balance(USER, NATIVE_DENOM).unwrap().amount
+        }
+
+        #[test]
+        fn scheduled_payments_are_claimed_each_interval() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let starting_balance = user_balance(&mut app);
+
+            // the interval has to be between a day and about ten years
+            for interval_in_days in [0, 3651, u64::MAX] {
+                let msg = ExecuteMsg::CreateSchedule {
+                    recipient: USER.to_string(),
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(100_000),
+                    interval_in_days,
+                    count: 3,
+                };
+                let err = app
+                    .execute_contract(
+                        Addr::unchecked(WITHDRAW_ADDRESS),
+                        contract_addr.clone(),
+                        &msg,
+                        &[],
+                    )
+                    .unwrap_err();
+                assert_eq!(ContractError::InvalidSchedule {}, err.downcast().unwrap());
+            }
+
+            create_schedule(&mut app, contract_addr.clone(), 3);
+
+            // nothing is due until the withdraw delay has passed
+            let err = claim(&mut app, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::ScheduleNotDue {}, err.downcast().unwrap());
+
+            app.update_block(advance_one_day_one_hour);
+            claim(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(100_000)
+            );
+
+            // the next one is a whole interval away
+            let err = claim(&mut app, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::ScheduleNotDue {}, err.downcast().unwrap());
+
+            // anything missed is caught up on, up to the count
+            for _ in 0..60 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            claim(&mut app, contract_addr.clone()).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(300_000)
+            );
+
+            // and once it's all paid out, it's gone
+            let schedules: SchedulesResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    contract_addr,
+                    &QueryMsg::ListSchedules {
+                        start_after: None,
+                        limit: None,
+                    },
+                )
+                .unwrap();
+            assert!(schedules.schedules.is_empty());
+        }
+
+        #[test]
+        fn schedules_can_be_paused_and_cancelled() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            create_schedule(&mut app, contract_addr.clone(), 12);
+
+            let msg = ExecuteMsg::PauseSchedule { id: 1 };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            app.update_block(advance_one_day_one_hour);
+            let err = claim(&mut app, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::SchedulePaused {}, err.downcast().unwrap());
+
+            // only the withdraw_address can resume it
+            let msg = ExecuteMsg::ResumeSchedule { id: 1 };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(OVERRIDE_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            // which waits out the withdraw delay again
+            let err = claim(&mut app, contract_addr.clone()).unwrap_err();
+            assert_eq!(ContractError::ScheduleNotDue {}, err.downcast().unwrap());
+            app.update_block(advance_one_day_one_hour);
+            claim(&mut app, contract_addr.clone()).unwrap();
+
+            let msg = ExecuteMsg::CancelSchedule { id: 1 };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+            for _ in 0..30 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            claim(&mut app, contract_addr).unwrap_err();
+        }
+    }
+
+    mod streams {
+        use super::*;
+
+        use crate::msg::{StreamResponse, WithdrawableResponse};
+        use cosmwasm_std::Timestamp;
+
+        // streams 1ujuno a second
+        fn create_stream(app: &mut App, contract_address: Addr, start: Timestamp) {
+            let msg = ExecuteMsg::CreateStream {
+                recipient: USER.to_string(),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                total: Uint128::new(1_000_000),
+                start,
+                end: start.plus_seconds(1_000_000),
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+            .unwrap();
+        }
+
+        fn withdraw_from_stream(
+            app: &mut App,
+            contract_address: Addr,
+            sender: &str,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::WithdrawFromStream { id: 1 };
+            app.execute_contract(Addr::unchecked(sender), contract_address, &msg, &[])
+        }
+
+        fn get_stream(app: &mut App, contract_address: Addr) -> StreamResponse {
+            app.wrap()
+                .query_wasm_smart(contract_address, &QueryMsg::GetStream { id: 1 })
+                .unwrap()
+        }
+
+        fn user_balance(app: &mut App) -> Uint128 {
+            app.wrap().query_balance(USER, NATIVE_DENOM).unwrap().amount
+        }
+
+        #[test]
+        fn streams_accrue_every_second() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let starting_balance = user_balance(&mut app);
+
+            // it has to start after the withdraw delay
+            let now = app.block_info().time;
+            let msg = ExecuteMsg::CreateStream {
+                recipient: USER.to_string(),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                total: Uint128::new(1_000_000),
+                start: now,
+                end: now.plus_seconds(1_000_000),
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::StreamStartsTooSoon {
+                    earliest: now.plus_seconds(86400)
+                },
+                err.downcast().unwrap()
+            );
+
+            let start = now.plus_seconds(2 * 86400);
+            create_stream(&mut app, contract_addr.clone(), start);
+            let err = withdraw_from_stream(&mut app, contract_addr.clone(), USER).unwrap_err();
+            assert_eq!(ContractError::NothingToWithdraw {}, err.downcast().unwrap());
+
+            app.update_block(|block| block.time = start.plus_seconds(250_000));
+            let stream = get_stream(&mut app, contract_addr.clone());
+            assert_eq!(stream.streamed, Uint128::new(250_000));
+            assert_eq!(stream.remaining, Uint128::new(750_000));
+
+            // only the recipient can withdraw
+            let err = withdraw_from_stream(&mut app, contract_addr.clone(), WITHDRAW_ADDRESS)
+                .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            withdraw_from_stream(&mut app, contract_addr.clone(), USER).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(250_000)
+            );
+
+            let stream = get_stream(&mut app, contract_addr);
+            assert_eq!(stream.withdrawn, Uint128::new(250_000));
+            assert_eq!(stream.withdrawable, Uint128::zero());
+        }
+
+        #[test]
+        fn cancelling_a_stream_keeps_what_has_accrued() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let start = app.block_info().time.plus_seconds(2 * 86400);
+            create_stream(&mut app, contract_addr.clone(), start);
+            app.update_block(|block| block.time = start.plus_seconds(400_000));
+
+            // only the override_address can cancel it
+            let msg = ExecuteMsg::CancelStream { id: 1 };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &msg,
+                &[],
+            )
+            .unwrap();
+
+            // nothing more accrues
+            app.update_block(|block| block.time = start.plus_seconds(1_000_000));
+            let stream = get_stream(&mut app, contract_addr.clone());
+            assert_eq!(stream.streamed, Uint128::new(400_000));
+            assert_eq!(stream.remaining, Uint128::zero());
+
+            // but what had accrued can still be withdrawn
+            withdraw_from_stream(&mut app, contract_addr.clone(), USER).unwrap();
+            assert_eq!(
+                get_balance(&mut app, &contract_addr),
+                vec![Coin::new(2_600_000, NATIVE_DENOM)]
+            );
+        }
+
+        #[test]
+        fn streamed_funds_are_set_aside() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let start = app.block_info().time.plus_seconds(2 * 86400);
+            create_stream(&mut app, contract_addr.clone(), start);
+
+            // the whole stream is out of reach of a withdrawal
+            let msg = ExecuteMsg::StartWithdraw {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                amount: Uint128::new(2_500_000),
+                auto_undelegate: false,
+                use_reserve_delay: false,
+                vault: None,
+                category: None,
+            };
+            let err = app
+                .execute_contract(
+                    Addr::unchecked(WITHDRAW_ADDRESS),
+                    contract_addr.clone(),
+                    &msg,
+                    &[],
+                )
+                .unwrap_err();
+            assert_eq!(
+                ContractError::InsufficientVaultBalance {},
+                err.downcast().unwrap()
+            );
+
+            // and withdrawing from the stream only spends what was set aside
+            app.update_block(|block| block.time = start.plus_seconds(400_000));
+            withdraw_from_stream(&mut app, contract_addr.clone(), USER).unwrap();
+            let msg = QueryMsg::GetWithdrawable {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                vault: None,
+            };
+            let withdrawable: WithdrawableResponse = app
+                .wrap()
+                .query_wasm_smart(contract_addr.clone(), &msg)
+                .unwrap();
+            assert_eq!(withdrawable.withdrawable, Uint128::new(2_000_000));
+
+            // cancelling hands back whatever hadn't accrued
+            let cancel = ExecuteMsg::CancelStream { id: 1 };
+            app.execute_contract(
+                Addr::unchecked(OVERRIDE_ADDRESS),
+                contract_addr.clone(),
+                &cancel,
+                &[],
+            )
+            .unwrap();
+            let withdrawable: WithdrawableResponse =
+                app.wrap().query_wasm_smart(contract_addr, &msg).unwrap();
+            assert_eq!(withdrawable.withdrawable, Uint128::new(2_600_000));
+        }
+    }
+
+    mod agreements {
+        use super::*;
+
+        use crate::msg::{MilestoneTerms, WithdrawableResponse};
+        use crate::state::{Agreement, MilestoneStatus};
+
+        const ARBITER: &str = "arbiter";
+
+        // two milestones of 500_000, due in 10 and 20 days
+        fn create_agreement(app: &mut App, contract_address: Addr) {
+            let now = app.block_info().time;
+            let msg = ExecuteMsg::CreateAgreement {
+                payee: USER.to_string(),
+                arbiter: Some(ARBITER.to_string()),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                milestones: vec![
+                    MilestoneTerms {
+                        amount: Uint128::new(500_000),
+                        deadline: now.plus_seconds(10 * 86400),
+                    },
+                    MilestoneTerms {
+                        amount: Uint128::new(500_000),
+                        deadline: now.plus_seconds(20 * 86400),
+                    },
+                ],
+            };
+            app.execute_contract(
+                Addr::unchecked(WITHDRAW_ADDRESS),
+                contract_address,
+                &msg,
+                &[],
+            )
+            .unwrap();
+        }
+
+        fn execute(
+            app: &mut App,
+            contract_address: Addr,
+            sender: &str,
+            msg: ExecuteMsg,
+        ) -> anyhow::Result<AppResponse> {
+            app.execute_contract(Addr::unchecked(sender), contract_address, &msg, &[])
+        }
+
+        fn get_withdrawable(app: &mut App, contract_address: Addr) -> Uint128 {
+            let msg = QueryMsg::GetWithdrawable {
+                denom_or_address: NATIVE_DENOM.to_string(),
+                vault: None,
+            };
+            let result: WithdrawableResponse =
+                app.wrap().query_wasm_smart(contract_address, &msg).unwrap();
+            result.withdrawable
+        }
+
+        fn user_balance(app: &mut App) -> Uint128 {
+            app.wrap().query_balance(USER, NATIVE_DENOM).unwrap().amount
+        }
+
+        #[test]
+        fn milestones_are_approved_or_refunded() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let starting_balance = user_balance(&mut app);
+            create_agreement(&mut app, contract_addr.clone());
+
+            // the total is set aside
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr.clone()),
+                Uint128::new(2_000_000)
+            );
+
+            // nothing can be paid until the withdraw delay has passed
+            let approve = ExecuteMsg::ApproveMilestone {
+                id: 1,
+                milestone: 0,
+            };
+            let err = execute(
+                &mut app,
+                contract_addr.clone(),
+                WITHDRAW_ADDRESS,
+                approve.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(
+                ContractError::AgreementNotActive {},
+                err.downcast().unwrap()
+            );
+
+            app.update_block(advance_one_day_one_hour);
+            execute(&mut app, contract_addr.clone(), WITHDRAW_ADDRESS, approve).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(500_000)
+            );
+
+            // the second can only be refunded once its deadline has passed
+            let refund = ExecuteMsg::RefundMilestone {
+                id: 1,
+                milestone: 1,
+            };
+            let err =
+                execute(&mut app, contract_addr.clone(), "anyone", refund.clone()).unwrap_err();
+            assert_eq!(ContractError::DeadlineNotPassed {}, err.downcast().unwrap());
+            for _ in 0..20 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            execute(&mut app, contract_addr.clone(), "anyone", refund).unwrap();
+
+            // and then it can be withdrawn again
+            assert_eq!(
+                get_withdrawable(&mut app, contract_addr),
+                Uint128::new(2_500_000)
+            );
+        }
+
+        #[test]
+        fn disputes_are_settled_by_the_arbiter_or_override() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let starting_balance = user_balance(&mut app);
+            create_agreement(&mut app, contract_addr.clone());
+            app.update_block(advance_one_day_one_hour);
+
+            for milestone in 0..2 {
+                let msg = ExecuteMsg::DisputeMilestone { id: 1, milestone };
+                execute(&mut app, contract_addr.clone(), USER, msg).unwrap();
+            }
+
+            // a disputed milestone can't be refunded
+            for _ in 0..10 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            let msg = ExecuteMsg::RefundMilestone {
+                id: 1,
+                milestone: 0,
+            };
+            let err = execute(&mut app, contract_addr.clone(), "anyone", msg).unwrap_err();
+            assert_eq!(ContractError::MilestoneSettled {}, err.downcast().unwrap());
+
+            // only the arbiter or the override_address can settle it
+            let msg = ExecuteMsg::ResolveDispute {
+                id: 1,
+                milestone: 0,
+                release: true,
+            };
+            let err = execute(
+                &mut app,
+                contract_addr.clone(),
+                WITHDRAW_ADDRESS,
+                msg.clone(),
+            )
+            .unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+            execute(&mut app, contract_addr.clone(), ARBITER, msg).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(500_000)
+            );
+
+            let msg = ExecuteMsg::ResolveDispute {
+                id: 1,
+                milestone: 1,
+                release: false,
+            };
+            execute(&mut app, contract_addr.clone(), OVERRIDE_ADDRESS, msg).unwrap();
+
+            let agreement: Agreement = app
+                .wrap()
+                .query_wasm_smart(contract_addr, &QueryMsg::GetAgreement { id: 1 })
+                .unwrap();
+            assert_eq!(agreement.milestones[0].status, MilestoneStatus::Paid);
+            assert_eq!(agreement.milestones[1].status, MilestoneStatus::Refunded);
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(500_000)
+            );
+        }
+
+        #[test]
+        fn committed_milestones_are_paid_regardless_of_the_floor() {
+            let (mut app, _cw_template_contract, contract_addr, _, _) =
+                mock_instantiate(1, false, false);
+            let starting_balance = user_balance(&mut app);
+
+            // the arbiter can't be the payer
+            let msg = ExecuteMsg::CreateAgreement {
+                payee: USER.to_string(),
+                arbiter: Some(WITHDRAW_ADDRESS.to_string()),
+                denom_or_address: NATIVE_DENOM.to_string(),
+                milestones: vec![MilestoneTerms {
+                    amount: Uint128::new(500_000),
+                    deadline: app.block_info().time.plus_seconds(10 * 86400),
+                }],
+            };
+            let err = execute(&mut app, contract_addr.clone(), WITHDRAW_ADDRESS, msg).unwrap_err();
+            assert_eq!(ContractError::InvalidAgreement {}, err.downcast().unwrap());
+
+            create_agreement(&mut app, contract_addr.clone());
+
+            // a floor set afterwards doesn't touch what was already set aside
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: NATIVE_DENOM.to_string(),
+                    amount: Uint128::new(2_500_000),
+                },
+            );
+            let approve = ExecuteMsg::ApproveMilestone {
+                id: 1,
+                milestone: 0,
+            };
+            execute(&mut app, contract_addr.clone(), WITHDRAW_ADDRESS, approve).unwrap();
+            assert_eq!(
+                user_balance(&mut app),
+                starting_balance + Uint128::new(500_000)
+            );
+
+            // but a new agreement has to fit above it
+            let now = app.block_info().time;
+            let msg = ExecuteMsg::CreateAgreement {
+                payee: USER.to_string(),
+                arbiter: None,
+                denom_or_address: NATIVE_DENOM.to_string(),
+                milestones: vec![MilestoneTerms {
+                    amount: Uint128::new(500_000),
+                    deadline: now.plus_seconds(10 * 86400),
+                }],
+            };
+            let err = execute(&mut app, contract_addr, WITHDRAW_ADDRESS, msg).unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(2_500_000)
+                },
+                err.downcast().unwrap()
+            );
+        }
+    }
+
+    mod deposit_refunds {
+        use super::*;
+
+        // deposits can be refunded by the depositor for 2 days
+        fn mock_instantiate_with_refunds() -> (App, Addr, Addr) {
+            let msg = InstantiateMsg {
+                enable_cw20_receive: true,
+                deposit_refund_period_in_days: Some(2),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+
+            (app, contract_addr, cw20_contract_addr)
+        }
+
+        fn refund_deposit(
+            app: &mut App,
+            contract_address: Addr,
+            sender: &str,
+            id: u64,
+        ) -> anyhow::Result<AppResponse> {
+            let msg = ExecuteMsg::RefundDeposit { id };
+            app.execute_contract(Addr::unchecked(sender), contract_address, &msg, &[])
+        }
+
+        #[test]
+        fn depositors_can_refund_within_the_period() {
+            let (mut app, contract_addr, cw20_contract_addr) = mock_instantiate_with_refunds();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // only the depositor or override_address can refund it
+            let err =
+                refund_deposit(&mut app, contract_addr.clone(), WITHDRAW_ADDRESS, 1).unwrap_err();
+            assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+            refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap();
+            assert_eq!(
+                get_cw20_balance(&mut app, cw20_contract_addr.clone(), USER.to_string()),
+                Uint128::new(1_000_000)
+            );
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap_err();
+            assert_eq!(
+                ContractError::DepositAlreadyRefunded {},
+                err.downcast().unwrap()
+            );
+
+            // once the period has passed, only the override_address can refund it
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+            for _ in 0..2 {
+                app.update_block(advance_one_day_one_hour);
+            }
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 2).unwrap_err();
+            assert_eq!(
+                ContractError::RefundPeriodPassed {},
+                err.downcast().unwrap()
+            );
+            refund_deposit(&mut app, contract_addr, OVERRIDE_ADDRESS, 2).unwrap();
+            assert_eq!(
+                get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string()),
+                Uint128::new(1_000_000)
+            );
+        }
+
+        #[test]
+        fn withdrawing_roles_cant_refund_themselves() {
+            // the depositor is the withdraw_address
+            let (mut app, contract_addr, cw20_contract_addr) = mock_instantiate_with_refunds();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+            exec_update_withdraw_address(
+                &mut app,
+                OVERRIDE_ADDRESS.to_string(),
+                contract_addr.clone(),
+                USER.to_string(),
+            )
+            .unwrap();
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap_err();
+            assert_eq!(
+                ContractError::SelfRefundNotAllowed {},
+                err.downcast().unwrap()
+            );
+
+            // or the recovery_address
+            let msg = InstantiateMsg {
+                enable_cw20_receive: true,
+                deposit_refund_period_in_days: Some(2),
+                recovery_address: Some(USER.to_string()),
+                inactivity_period_in_days: Some(30),
+                ..mock_instantiate_msg(1, false, false)
+            };
+            let (mut app, _cw_template_contract, contract_addr, _, cw20_contract_addr) =
+                mock_instantiate_with_msg(msg);
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::AddAcceptedCw20 {
+                    address: cw20_contract_addr.to_string(),
+                },
+            );
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap_err();
+            assert_eq!(
+                ContractError::SelfRefundNotAllowed {},
+                err.downcast().unwrap()
+            );
+
+            // the override_address can still send it back
+            refund_deposit(&mut app, contract_addr, OVERRIDE_ADDRESS, 1).unwrap();
+            assert_eq!(
+                get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string()),
+                Uint128::new(1_000_000)
+            );
+        }
+
+        #[test]
+        fn refunds_never_touch_earmarked_funds() {
+            let (mut app, contract_addr, cw20_contract_addr) = mock_instantiate_with_refunds();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // the contract holds 5_100_000, and a withdrawal is counting on most of it
+            start_cw20_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(5_050_000),
+                cw20_contract_addr.to_string(),
+            )
+            .unwrap();
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap_err();
+            assert_eq!(ContractError::DepositEarmarked {}, err.downcast().unwrap());
+
+            // leaving enough for the refund
+            start_cw20_withdraw(
+                &mut app,
+                WITHDRAW_ADDRESS.to_string(),
+                contract_addr.clone(),
+                Uint128::new(5_000_000),
+                cw20_contract_addr.to_string(),
+            )
+            .unwrap();
+            refund_deposit(&mut app, contract_addr, USER, 1).unwrap();
+            assert_eq!(
+                get_cw20_balance(&mut app, cw20_contract_addr, USER.to_string()),
+                Uint128::new(1_000_000)
+            );
+        }
+
+        #[test]
+        fn refunds_respect_the_reserve_floor() {
+            let (mut app, contract_addr, cw20_contract_addr) = mock_instantiate_with_refunds();
+            send_cw20(
+                &mut app,
+                cw20_contract_addr.clone(),
+                contract_addr.clone(),
+                Uint128::new(100_000),
+                Binary::default(),
+            )
+            .unwrap();
+
+            // the contract holds 5_100_000, and most of it has to stay
+            exec_config_update(
+                &mut app,
+                contract_addr.clone(),
+                ConfigUpdate::SetReserveFloor {
+                    denom_or_address: cw20_contract_addr.to_string(),
+                    amount: Uint128::new(5_050_000),
+                },
+            );
+            let err = refund_deposit(&mut app, contract_addr.clone(), USER, 1).unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(5_050_000)
+                },
+                err.downcast().unwrap()
+            );
+
+            // the override_address can't get round it either
+            let err = refund_deposit(&mut app, contract_addr, OVERRIDE_ADDRESS, 1).unwrap_err();
+            assert_eq!(
+                ContractError::BelowReserveFloor {
+                    floor: Uint128::new(5_050_000)
+                },
+                err.downcast().unwrap()
+            );
+        }
+    }
 }
@@ -1,19 +1,50 @@
-use cosmwasm_std::{Timestamp, Uint128};
-use cw20::Cw20ReceiveMsg;
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, FullDelegation, Timestamp, Uint128};
+use cw20::{Cw20CoinVerified, Cw20ReceiveMsg};
+use cw721::Cw721ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::state::{
+    Agreement, Asset, ConfigUpdate, Deposit, Evacuation, ExecutionAllowList, GuardianKey,
+    LockableField, PendingConfigUpdate, Proposal, ReserveFloor, Schedule, Stream, Vault,
+    VaultTransfer, Withdrawal,
+};
+
 /// Basic configuration for the contract
 /// The contract will have no admin so this will need to be set correctly
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
-    pub enable_cw20_receive: bool, // should the cw20 receive functionality be enabled? (cannot be changed later)
-    pub set_override_as_immutable: bool, // should the override_address be updateable? (cannot be changed later)
-    pub set_withdraw_as_immutable: bool, // should the withdraw_address be updateable? (cannot be changed later)
+    pub enable_cw20_receive: bool, // should the cw20 receive functionality be enabled? (can be changed by a config update)
+    pub set_override_as_immutable: bool, // lock the override_address now? (can also be locked later)
+    pub set_withdraw_as_immutable: bool, // lock the withdraw_address now? (can also be locked later)
     pub override_address: String,        // the deadman switch address and admin
     pub withdraw_address: String,        // the address whose funds are locked in this contract
-    pub withdraw_delay_in_days: u64,     // withdraw delay in days
-    pub native_denom: String,            // native chain denom - presumably ujuno
+    pub withdraw_delay_in_days: u64,     // withdraw delay in days, at most 3650
+    pub native_denoms: Vec<String>, // native denoms that can be withdrawn - presumably including ujuno
+    pub recovery_address: Option<String>, // can start a withdrawal if the withdraw_address goes silent
+    pub inactivity_period_in_days: Option<u64>, // how long the withdraw_address can be silent for
+    pub override_heartbeat_period_in_days: Option<u64>, // how often the override_address must check in
+    pub fallback_override_address: Option<String>, // takes over withdrawal cancelling and the address updates if the override_address lapses
+    #[serde(default)]
+    pub accepted_cw20_addresses: Vec<String>, // the cw20s that can be deposited via the receive hook
+    #[serde(default)]
+    pub unbonding_period_in_days: Option<u64>, // the chain's unbonding period, used to warn on auto-undelegation
+    #[serde(default)]
+    pub execution_allow_list: Option<ExecutionAllowList>, // limits what proposed executions can do, anything goes if unset
+    #[serde(default)]
+    pub permissionless_execution: bool, // can anyone trigger a ready withdrawal? (can be changed by a config update)
+    #[serde(default)]
+    pub keeper_tip: Option<Coin>, // paid from the keeper pool to whoever triggers a ready withdrawal
+    #[serde(default)]
+    pub guardian_key: Option<GuardianKey>, // a cold key that can sign cancellations off-chain
+    #[serde(default)]
+    pub safe_harbor_address: Option<String>, // where the override_address can evacuate funds to (cannot be changed later)
+    #[serde(default)]
+    pub reserve_floors: Vec<ReserveFloor>, // the least of each asset that has to stay in escrow (can be changed by a config update)
+    #[serde(default)]
+    pub reserve_delay_in_days: Option<u64>, // the longer delay for dipping into the reserve, it can't be withdrawn if unset
+    #[serde(default)]
+    pub deposit_refund_period_in_days: Option<u64>, // how long a depositor has to take back a mistaken deposit
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -25,40 +56,300 @@ pub enum ExecuteMsg {
     /// the denom_or_address field should match either the
     /// CW20 contract corresponding to the token to be withdrawn
     /// or the native denom to be withdrawn
+    /// if auto_undelegate is set, and the denom is the staking denom
+    /// any shortfall in the liquid balance is undelegated
+    /// a withdrawal can't take the balance below its reserve floor
+    /// unless use_reserve_delay is set, which uses the reserve delay instead
+    /// if a vault is given, it comes out of that vault, on its delay
+    /// otherwise it comes out of the default vault
+    /// if a category is given, it has to fit in what's left of its budget
     /// this can only be executed by the withdrawal_address
     StartWithdraw {
         denom_or_address: String,
         amount: Uint128,
+        #[serde(default)]
+        auto_undelegate: bool,
+        #[serde(default)]
+        use_reserve_delay: bool,
+        #[serde(default)]
+        vault: Option<String>,
+        #[serde(default)]
+        category: Option<String>,
     },
     /// When the NATIVE funds are ready to be claimed,
     /// this allows them to actually be claimed
     /// specify the native denom
     /// this can only be executed by the withdrawal_address
+    /// or by anyone, if permissionless execution is on
     /// this also resets the timer once complete
     ExecuteNativeWithdraw { denom: String },
     /// When the CW20 funds are ready to be claimed,
     /// this allows them to be claimed
+    /// escrowed balances are debited first, then any untracked surplus
     /// takes the address of the CW20 balance to be claimed
     /// this can only be executed by the withdrawal_address
+    /// or by anyone, if permissionless execution is on
     ExecuteCW20Withdraw { address: String },
-    /// When any CW20 funds custodied by
-    /// this contract are claimable,
-    /// this allows them to be claimed
-    /// takes the address of the CW20 balance to be claimed
-    /// this can only be executed by the withdrawal_address
+    /// Kept for compatibility, this is the same as ExecuteCW20Withdraw
     ExecuteEscrowCW20Withdraw { address: String },
     /// If a withdrawal is in progress, cancel it
     /// this can only be executed by the override_address
     OverrideWithdraw {},
+    /// Cancels a withdrawal with a signature from the guardian_key
+    /// the signature is over the sha256 of
+    /// override_withdraw:{chain_id}:{contract_address}:{withdrawal_id}:{nonce}
+    /// and the nonce must be higher than the last one used
+    /// this can be executed by anyone
+    OverrideWithdrawWithSignature {
+        withdrawal_id: u64,
+        nonce: u64,
+        signature: Binary,
+    },
     /// Update the override_address
     /// this can only be executed by the override_address
+    /// additionally, it can be locked
     UpdateOverrideAddress { address: String },
     /// Update the withdrawal address
     /// this can only be executed by the override_address
-    /// additionally, it can be turned off on instantiate
+    /// additionally, it can be locked
     UpdateWithdrawalAddress { address: String },
-    ///
+    /// Gives up the override role for good, leaving a pure time-lock
+    /// the first call starts the renounce, and a second call
+    /// confirms it once the withdraw delay has passed
+    /// the fallback_override_address and guardian_key go with it
+    /// this can only be executed by the override_address
+    RenounceOverride {},
+    /// Cancels a pending renounce
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelRenounceOverride {},
+    /// Locks part of the config, so it can never be changed again
+    /// this can't be undone
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    /// but only the override_address can lock either address
+    LockField { field: LockableField },
+    /// Proves the sender is still alive
+    /// and resets its inactivity timer
+    /// if sent by the withdrawal_address, any pending
+    /// recovery withdrawal is cancelled
+    /// if sent by the override_address, it takes back
+    /// its powers from the fallback_override_address
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    Heartbeat {},
+    /// Starts a withdrawal on behalf of a withdrawal address
+    /// that has been silent for longer than the inactivity period
+    /// this is subject to the same delay as StartWithdraw
+    /// and can be cancelled by the override_address
+    /// the funds are claimed by the recovery_address
+    /// this can only be executed by the recovery_address
+    StartRecoveryWithdraw {
+        denom_or_address: String,
+        amount: Uint128,
+    },
+    /// Proposes a change to the config, such as the list
+    /// of accepted CW20s, which can be applied once
+    /// the withdraw delay has passed
+    /// this fails while another config update is pending
+    /// this can only be executed by the override_address
+    StartConfigUpdate { update: ConfigUpdate },
+    /// Applies the pending config update once it is ready
+    /// this can only be executed by the override_address
+    ExecuteConfigUpdate {},
+    /// Cancels the pending config update
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelConfigUpdate {},
+    /// Reconciles the escrowed balance of a CW20
+    /// with what this contract actually holds
+    /// any surplus or deficit is logged in the response
+    /// takes the address of the CW20 contract
+    /// this can only be executed by the override_address
+    SyncCw20Balance { address: String },
+    /// Deposits CW20s by pulling them in with TransferFrom
+    /// the sender must first grant this contract an allowance
+    /// the CW20 must be on the accepted list
+    /// this can be executed by anyone
+    DepositCw20 { token: String, amount: Uint128 },
+    /// CW20 receive hook, called by a CW20 contract
+    /// when tokens are sent to this contract
     Receive(Cw20ReceiveMsg),
+    /// Starts a withdrawal of escrowed NFTs from a collection
+    /// this is subject to the same delay as StartWithdraw
+    /// and can be cancelled by the override_address
+    /// this can only be executed by the withdrawal_address
+    StartNftWithdraw {
+        collection: String,
+        token_ids: Vec<String>,
+    },
+    /// When the NFTs are ready to be claimed,
+    /// this transfers them to the withdrawal_address
+    /// takes the address of the CW721 collection
+    /// this can only be executed by the withdrawal_address
+    /// or by anyone, if permissionless execution is on
+    ExecuteNftWithdraw { collection: String },
+    /// CW721 receive hook, called by a CW721 contract
+    /// when an NFT is sent to this contract
+    ReceiveNft(Cw721ReceiveMsg),
+    /// Delegates escrowed funds of the staking denom to a validator
+    /// the funds stay in escrow, bonded to this contract
+    /// this can only be executed by the withdrawal_address
+    Delegate { validator: String, amount: Uint128 },
+    /// Starts unbonding funds from a validator
+    /// once unbonded they are back in the escrow balance
+    /// this can only be executed by the withdrawal_address
+    Undelegate { validator: String, amount: Uint128 },
+    /// Moves delegated funds from one validator to another
+    /// this can only be executed by the withdrawal_address
+    Redelegate {
+        src_validator: String,
+        dst_validator: String,
+        amount: Uint128,
+    },
+    /// Claims staking rewards from every validator delegated to
+    /// the rewards are paid into escrow
+    /// this can only be executed by the withdrawal_address
+    ClaimRewards {},
+    /// Proposes a set of messages for this contract to execute
+    /// once the withdraw delay has passed
+    /// if an execution allow-list is set, every message must be on it
+    /// this can only be executed by the withdrawal_address
+    ProposeExecution { msgs: Vec<CosmosMsg> },
+    /// Executes a proposal once it is ready
+    /// this can only be executed by the withdrawal_address
+    ExecuteProposal { id: u64 },
+    /// Cancels a pending proposal
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelProposal { id: u64 },
+    /// Adds the native funds sent to the keeper pool
+    /// which is used to tip keepers, and nothing else
+    /// this can be executed by anyone
+    FundKeeperPool {},
+    /// Moves escrowed assets to the safe_harbor_address straight away
+    /// for when the withdrawal_address has been compromised
+    /// the funds can only ever go to the safe_harbor_address
+    /// and every evacuation is recorded
+    /// this can only be executed by the override_address
+    Evacuate { assets: Vec<Asset> },
+    /// Moves an asset from one vault to another
+    /// a vault of None is the default vault
+    /// into a vault that's at least as cold, this is instant
+    /// otherwise it waits out the delay of the vault it leaves
+    /// this can only be executed by the withdrawal_address
+    StartVaultTransfer {
+        from: Option<String>,
+        to: Option<String>,
+        denom_or_address: String,
+        amount: Uint128,
+    },
+    /// Completes a move between vaults once it is ready
+    /// this can only be executed by the withdrawal_address
+    ExecuteVaultTransfer { id: u64 },
+    /// Cancels a pending move between vaults
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelVaultTransfer { id: u64 },
+    /// Sets up a recurring payout of amount every interval, count times
+    /// the first instalment is due once the withdraw delay has passed
+    /// this can only be executed by the withdrawal_address
+    CreateSchedule {
+        recipient: String,
+        denom_or_address: String,
+        amount: Uint128,
+        interval_in_days: u64,
+        count: u64,
+    },
+    /// Pays out every instalment of a schedule that is due
+    /// this can be executed by anyone
+    ClaimScheduledPayment { id: u64 },
+    /// Stops a schedule paying out until it is resumed
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    PauseSchedule { id: u64 },
+    /// Resumes a paused schedule, once the withdraw delay has passed
+    /// this can only be executed by the withdrawal_address
+    ResumeSchedule { id: u64 },
+    /// Cancels a schedule, anything already paid out stays paid
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelSchedule { id: u64 },
+    /// Streams total to the recipient, accruing every second from start to end
+    /// the start has to be after the withdraw delay
+    /// this can only be executed by the withdrawal_address
+    CreateStream {
+        recipient: String,
+        denom_or_address: String,
+        total: Uint128,
+        start: Timestamp,
+        end: Timestamp,
+    },
+    /// Withdraws everything that has accrued so far
+    /// this can only be executed by the stream's recipient
+    WithdrawFromStream { id: u64 },
+    /// Stops a stream accruing, anything unaccrued stays in escrow
+    /// what has already accrued can still be withdrawn
+    /// this can only be executed by the override_address
+    CancelStream { id: u64 },
+    /// Sets up a grant paid against milestones, with the withdrawal_address as payer
+    /// the total is set aside until each milestone is settled
+    /// nothing can be paid out until the withdraw delay has passed
+    /// this can only be executed by the withdrawal_address
+    CreateAgreement {
+        payee: String,
+        arbiter: Option<String>,
+        denom_or_address: String,
+        milestones: Vec<MilestoneTerms>,
+    },
+    /// Cancels an agreement before it becomes active
+    /// this can be executed by the withdrawal_address
+    /// or the override_address
+    CancelAgreement { id: u64 },
+    /// Pays out a milestone, milestones are numbered from 0
+    /// this can only be executed by the withdrawal_address
+    ApproveMilestone { id: u64, milestone: u64 },
+    /// Disputes a milestone before its deadline, so it can't be refunded
+    /// this can be executed by the withdrawal_address
+    /// or the payee
+    DisputeMilestone { id: u64, milestone: u64 },
+    /// Settles a disputed milestone, paying it out if release is set
+    /// or refunding it otherwise
+    /// this can be executed by the arbiter
+    /// or the override_address
+    ResolveDispute {
+        id: u64,
+        milestone: u64,
+        release: bool,
+    },
+    /// Refunds a milestone that wasn't approved or disputed by its deadline
+    /// this can be executed by anyone
+    RefundMilestone { id: u64, milestone: u64 },
+    /// Sends a CW20 deposit back to its depositor
+    /// funds earmarked by a pending withdrawal are never refunded
+    /// this can be executed by the depositor, within the deposit refund period
+    /// unless it's the withdraw_address or recovery_address
+    /// or the override_address, at any time
+    RefundDeposit { id: u64 },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MilestoneTerms {
+    pub amount: Uint128,
+    pub deadline: Timestamp,
+}
+
+/// The message CW20s can attach when sending to this contract
+/// an empty message is treated as a plain Deposit
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    /// A plain deposit into the escrow
+    Deposit {},
+    /// A deposit, recorded along with a memo
+    DepositWithMemo { memo: String },
+    /// A deposit credited to a named vault, which has to have been set up
+    DepositToVault { vault: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -75,6 +366,115 @@ pub enum QueryMsg {
     /// Checks if a withdrawal has been requested
     /// i.e. if the withdrawal requested is None
     GetWithdrawalRequested {},
+    /// Gets the last time the withdrawal address checked in
+    /// and, if recovery is configured, when it becomes available
+    GetLastHeartbeat {},
+    /// Reports on the liveness of the withdrawal and override roles
+    /// and which address currently holds the override powers
+    GetSecurityStatus {},
+    /// Gets the balance of a CW20 escrowed via the receive hook
+    /// takes the address of the CW20 contract
+    GetCw20Balance { address: String },
+    /// Gets the total a depositor has sent of a CW20
+    /// via the receive hook
+    GetCw20Deposit { address: String, depositor: String },
+    /// Lists the CW20s that can be deposited via the receive hook
+    ListAcceptedTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Gets the withdrawal in progress, if any
+    GetPendingWithdrawal {},
+    /// Gets the config update waiting out the delay, if any
+    GetPendingConfigUpdate {},
+    /// Lists the CW20 deposits made via the receive hook
+    ListDeposits {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Compares every escrowed CW20 balance
+    /// against what this contract actually holds
+    VerifyBalances {},
+    /// Lists the native denoms and accepted CW20s
+    /// along with this contract's current balance of each
+    ListSupportedAssets {},
+    /// Gets the balance of an asset in a vault, less anything waiting to move out
+    /// None is the default vault
+    GetVaultBalance {
+        vault: Option<String>,
+        denom_or_address: String,
+    },
+    /// Gets a proposed execution by id
+    GetProposal { id: u64 },
+    /// Lists every proposed execution, whatever its status
+    ListProposals {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Gets the keeper pool, and the tip paid from it
+    GetKeeperPool {},
+    /// Lists the delegations made from escrow
+    /// along with any rewards waiting to be claimed
+    ListDelegations {},
+    /// Lists the NFTs held in escrow from a CW721 collection
+    ListNfts {
+        collection: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Gets how much of a native denom or CW20 can be withdrawn
+    /// from a vault on its delay, above the reserve floor
+    /// a vault of None is the default vault
+    GetWithdrawable {
+        denom_or_address: String,
+        #[serde(default)]
+        vault: Option<String>,
+    },
+    /// Lists the named vaults, with their delays and limits
+    ListVaults {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Lists the moves between vaults that are waiting out a delay
+    ListVaultTransfers {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Shows a budget category's allocations for the current period
+    /// along with what's been spent, and what's pending
+    GetBudget { category: String },
+    /// Shows a stream, with how much has streamed, been withdrawn, and is left
+    GetStream { id: u64 },
+    /// Shows a milestone agreement
+    GetAgreement { id: u64 },
+    /// Lists milestone agreements
+    ListAgreements {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists the recurring payouts that haven't finished
+    ListSchedules {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Lists every evacuation to the safe_harbor_address
+    ListEvacuations {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+/// Rebuilds the CW20 ledger, which was previously keyed
+/// by depositor rather than by CW20 contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MigrateMsg {
+    /// the CW20 contracts whose balances should be tracked
+    /// each is set to the balance this contract actually holds
+    pub cw20_addresses: Vec<String>,
+    /// the native denoms that can be withdrawn
+    /// older versions only stored a single, unused native_denom
+    #[serde(default)]
+    pub native_denoms: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -91,3 +491,174 @@ pub struct WithdrawalReadyResponse {
 pub struct WithdrawalRequestedResponse {
     pub withdrawal_requested: bool,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LastHeartbeatResponse {
+    pub last_heartbeat: Timestamp,
+    pub recovery_available_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SecurityStatusResponse {
+    pub withdraw_address_last_heartbeat: Timestamp,
+    pub withdraw_address_inactive: bool,
+    pub override_address_last_heartbeat: Timestamp,
+    pub override_address_lapsed: bool,
+    pub active_override_address: Option<Addr>,
+    pub last_guardian_nonce: Option<u64>,
+    pub override_renounce_ready_at: Option<Timestamp>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Cw20BalanceResponse {
+    pub address: Addr,
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Cw20DepositResponse {
+    pub address: Addr,
+    pub depositor: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AcceptedTokensResponse {
+    pub tokens: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingConfigUpdateResponse {
+    pub pending_config_update: Option<PendingConfigUpdate>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DepositsResponse {
+    pub deposits: Vec<Deposit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VaultBalanceResponse {
+    pub vault: Option<String>,
+    pub denom_or_address: String,
+    pub balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Cw20BalanceCheck {
+    pub address: Addr,
+    pub ledger_balance: Uint128,
+    pub actual_balance: Uint128,
+    pub surplus: Uint128,
+    pub deficit: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VerifyBalancesResponse {
+    pub balances: Vec<Cw20BalanceCheck>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SupportedAssetsResponse {
+    pub native: Vec<Coin>,
+    pub cw20: Vec<Cw20CoinVerified>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EscrowedNft {
+    pub collection: Addr,
+    pub token_id: String,
+    pub depositor: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct NftsResponse {
+    pub nfts: Vec<EscrowedNft>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DelegationsResponse {
+    pub delegations: Vec<FullDelegation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ProposalsResponse {
+    pub proposals: Vec<Proposal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct KeeperPoolResponse {
+    pub pool: Vec<Coin>,
+    pub keeper_tip: Option<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingWithdrawalResponse {
+    pub withdrawal: Option<Withdrawal>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EvacuationsResponse {
+    pub evacuations: Vec<Evacuation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WithdrawableResponse {
+    pub denom_or_address: String,
+    pub vault: Option<String>,
+    // what's in the vault, less anything waiting to move out
+    pub balance: Uint128,
+    pub reserve_floor: Uint128,
+    pub withdrawable: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VaultsResponse {
+    pub vaults: Vec<Vault>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VaultTransfersResponse {
+    pub transfers: Vec<VaultTransfer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SchedulesResponse {
+    pub schedules: Vec<Schedule>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct StreamResponse {
+    pub stream: Stream,
+    // accrued so far, whether or not it's been withdrawn
+    pub streamed: Uint128,
+    pub withdrawn: Uint128,
+    // accrued, but not yet withdrawn
+    pub withdrawable: Uint128,
+    // still to accrue
+    pub remaining: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AgreementsResponse {
+    pub agreements: Vec<Agreement>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BudgetResponse {
+    pub category: String,
+    pub period_in_days: u64,
+    pub period_start: Timestamp,
+    pub period_end: Timestamp,
+    pub lines: Vec<BudgetLine>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BudgetLine {
+    pub denom_or_address: String,
+    pub allocated: Uint128,
+    pub spent: Uint128,
+    // started, but not yet claimed
+    pub pending: Uint128,
+    pub remaining: Uint128,
+}
@@ -1,9 +1,30 @@
-use cosmwasm_std::{Deps, Env, StdError, StdResult};
+use cosmwasm_std::{Coin, Deps, Env, Order, StdError, StdResult, Uint128};
+use cw20::Cw20CoinVerified;
+use cw_storage_plus::Bound;
 
+use crate::helpers::{
+    budget_pending, budget_spent, check_cw20_balance, query_cw20_balance,
+    query_uncommitted_balance, query_vault_balance,
+};
 use crate::msg::{
-    WithdrawalReadyResponse, WithdrawalRequestedResponse, WithdrawalTimestampResponse,
+    AcceptedTokensResponse, AgreementsResponse, BudgetLine, BudgetResponse, Cw20BalanceResponse,
+    Cw20DepositResponse, DelegationsResponse, DepositsResponse, EscrowedNft, EvacuationsResponse,
+    KeeperPoolResponse, LastHeartbeatResponse, NftsResponse, PendingConfigUpdateResponse,
+    PendingWithdrawalResponse, ProposalsResponse, SchedulesResponse, SecurityStatusResponse,
+    StreamResponse, SupportedAssetsResponse, VaultBalanceResponse, VaultTransfersResponse,
+    VaultsResponse, VerifyBalancesResponse, WithdrawableResponse, WithdrawalReadyResponse,
+    WithdrawalRequestedResponse, WithdrawalTimestampResponse,
+};
+use crate::state::{
+    Agreement, Config, Proposal, ACCEPTED_CW20S, AGREEMENTS, BUDGET_CATEGORIES, CONFIG,
+    CW20_BALANCES, CW20_DEPOSITS, DEPOSITS, EVACUATIONS, KEEPER_POOL, LAST_GUARDIAN_NONCE,
+    LAST_OVERRIDE_HEARTBEAT, LAST_WITHDRAW_HEARTBEAT, NATIVE_DENOMS, NFTS, PENDING_CONFIG_UPDATE,
+    PENDING_RENOUNCE, PROPOSALS, SCHEDULES, STREAMS, VAULTS, VAULT_TRANSFERS, WITHDRAWAL_READY,
 };
-use crate::state::{Config, CONFIG, WITHDRAWAL_READY};
+
+// pagination defaults
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 pub fn query_config(deps: Deps) -> StdResult<Config> {
     let config = CONFIG.load(deps.storage)?;
@@ -54,3 +75,420 @@ pub fn get_withdraw_requested(deps: Deps, _env: Env) -> StdResult<WithdrawalRequ
         }),
     }
 }
+
+pub fn get_last_heartbeat(deps: Deps) -> StdResult<LastHeartbeatResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let last_heartbeat = LAST_WITHDRAW_HEARTBEAT.load(deps.storage)?;
+
+    Ok(LastHeartbeatResponse {
+        last_heartbeat,
+        recovery_available_at: config.recovery_available_at(last_heartbeat),
+    })
+}
+
+pub fn get_security_status(deps: Deps, env: Env) -> StdResult<SecurityStatusResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let withdraw_heartbeat = LAST_WITHDRAW_HEARTBEAT.load(deps.storage)?;
+    let override_heartbeat = LAST_OVERRIDE_HEARTBEAT.load(deps.storage)?;
+    let now = env.block.time;
+
+    let withdraw_address_inactive =
+        matches!(config.recovery_available_at(withdraw_heartbeat), Some(at) if now > at);
+    let override_address_lapsed =
+        matches!(config.override_lapses_at(override_heartbeat), Some(at) if now > at);
+
+    Ok(SecurityStatusResponse {
+        withdraw_address_last_heartbeat: withdraw_heartbeat,
+        withdraw_address_inactive,
+        override_address_last_heartbeat: override_heartbeat,
+        override_address_lapsed,
+        active_override_address: config.active_override_address(override_heartbeat, now),
+        last_guardian_nonce: LAST_GUARDIAN_NONCE.may_load(deps.storage)?,
+        override_renounce_ready_at: PENDING_RENOUNCE.may_load(deps.storage)?.flatten(),
+    })
+}
+
+pub fn get_cw20_balance(deps: Deps, address: String) -> StdResult<Cw20BalanceResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let balance = CW20_BALANCES
+        .may_load(deps.storage, address.clone())?
+        .unwrap_or_default();
+
+    Ok(Cw20BalanceResponse { address, balance })
+}
+
+pub fn get_cw20_deposit(
+    deps: Deps,
+    address: String,
+    depositor: String,
+) -> StdResult<Cw20DepositResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let depositor = deps.api.addr_validate(&depositor)?;
+    let amount = CW20_DEPOSITS
+        .may_load(deps.storage, (address.clone(), depositor.clone()))?
+        .unwrap_or_default();
+
+    Ok(Cw20DepositResponse {
+        address,
+        depositor,
+        amount,
+    })
+}
+
+pub fn list_accepted_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<AcceptedTokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+
+    let tokens = ACCEPTED_CW20S
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AcceptedTokensResponse { tokens })
+}
+
+pub fn get_pending_config_update(deps: Deps) -> StdResult<PendingConfigUpdateResponse> {
+    let pending_config_update = PENDING_CONFIG_UPDATE.may_load(deps.storage)?.flatten();
+
+    Ok(PendingConfigUpdateResponse {
+        pending_config_update,
+    })
+}
+
+pub fn list_deposits(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<DepositsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let deposits = DEPOSITS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, deposit)| deposit))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DepositsResponse { deposits })
+}
+
+pub fn get_vault_balance(
+    deps: Deps,
+    env: Env,
+    vault: Option<String>,
+    denom_or_address: String,
+) -> StdResult<VaultBalanceResponse> {
+    // a vault that was never set up is more likely a typo than empty
+    // and only a vault that's set up can be credited
+    if let Some(name) = &vault {
+        if !VAULTS.has(deps.storage, name.clone()) {
+            return Err(StdError::not_found(format!("Vault {}", name)));
+        }
+    }
+
+    let balance = query_vault_balance(
+        deps,
+        &env.contract.address,
+        vault.as_deref(),
+        &denom_or_address,
+    )?;
+
+    Ok(VaultBalanceResponse {
+        vault,
+        denom_or_address,
+        balance,
+    })
+}
+
+pub fn verify_balances(deps: Deps, env: Env) -> StdResult<VerifyBalancesResponse> {
+    let balances = CW20_BALANCES
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (cw20_addr, ledger_balance) = item?;
+            check_cw20_balance(
+                &deps.querier,
+                &cw20_addr,
+                &env.contract.address,
+                ledger_balance,
+            )
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(VerifyBalancesResponse { balances })
+}
+
+pub fn list_supported_assets(deps: Deps, env: Env) -> StdResult<SupportedAssetsResponse> {
+    let native = NATIVE_DENOMS
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|denom| deps.querier.query_balance(&env.contract.address, denom?))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let cw20 = ACCEPTED_CW20S
+        .keys(deps.storage, None, None, Order::Ascending)
+        .map(|cw20_addr| {
+            let cw20_addr = cw20_addr?;
+            let amount = query_cw20_balance(&deps.querier, &cw20_addr, &env.contract.address)?;
+            Ok(Cw20CoinVerified {
+                address: cw20_addr,
+                amount,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SupportedAssetsResponse { native, cw20 })
+}
+
+pub fn list_nfts(
+    deps: Deps,
+    collection: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NftsResponse> {
+    let collection = deps.api.addr_validate(&collection)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let nfts = NFTS
+        .prefix(collection.clone())
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let (token_id, depositor) = item?;
+            Ok(EscrowedNft {
+                collection: collection.clone(),
+                token_id,
+                depositor,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(NftsResponse { nfts })
+}
+
+pub fn list_delegations(deps: Deps, env: Env) -> StdResult<DelegationsResponse> {
+    let delegations = deps
+        .querier
+        .query_all_delegations(&env.contract.address)?
+        .into_iter()
+        .filter_map(|delegation| {
+            deps.querier
+                .query_delegation(&env.contract.address, delegation.validator)
+                .transpose()
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(DelegationsResponse { delegations })
+}
+
+pub fn get_proposal(deps: Deps, id: u64) -> StdResult<Proposal> {
+    PROPOSALS.load(deps.storage, id)
+}
+
+pub fn list_proposals(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<ProposalsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let proposals = PROPOSALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proposal)| proposal))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ProposalsResponse { proposals })
+}
+
+pub fn get_keeper_pool(deps: Deps) -> StdResult<KeeperPoolResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool = KEEPER_POOL
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(KeeperPoolResponse {
+        pool,
+        keeper_tip: config.keeper_tip,
+    })
+}
+
+pub fn get_pending_withdrawal(deps: Deps) -> StdResult<PendingWithdrawalResponse> {
+    let withdrawal = WITHDRAWAL_READY.may_load(deps.storage)?.flatten();
+
+    Ok(PendingWithdrawalResponse { withdrawal })
+}
+
+pub fn list_evacuations(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EvacuationsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let evacuations = EVACUATIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, evacuation)| evacuation))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(EvacuationsResponse { evacuations })
+}
+
+pub fn get_withdrawable(
+    deps: Deps,
+    env: Env,
+    denom_or_address: String,
+    vault: Option<String>,
+) -> StdResult<WithdrawableResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let total = query_uncommitted_balance(deps, &env.contract.address, &denom_or_address)?;
+    let balance = query_vault_balance(
+        deps,
+        &env.contract.address,
+        vault.as_deref(),
+        &denom_or_address,
+    )?;
+    // the floor is on everything escrowed that isn't set aside, whichever vault it's in
+    let reserve_floor = config.reserve_floor(&denom_or_address);
+
+    Ok(WithdrawableResponse {
+        denom_or_address,
+        vault,
+        balance,
+        reserve_floor,
+        withdrawable: balance.min(total.saturating_sub(reserve_floor)),
+    })
+}
+
+pub fn list_vaults(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<VaultsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let vaults = VAULTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, vault)| vault))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(VaultsResponse { vaults })
+}
+
+pub fn list_vault_transfers(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<VaultTransfersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let transfers = VAULT_TRANSFERS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, transfer)| transfer))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(VaultTransfersResponse { transfers })
+}
+
+pub fn get_budget(deps: Deps, env: Env, category: String) -> StdResult<BudgetResponse> {
+    let budget = BUDGET_CATEGORIES.load(deps.storage, category.clone())?;
+    let period = budget.period(env.block.time)?;
+
+    let lines = budget
+        .allocations
+        .iter()
+        .map(|allocation| {
+            let denom_or_address = &allocation.denom_or_address;
+            let spent = budget_spent(deps.storage, &category, denom_or_address, period)?;
+            let pending = budget_pending(deps.storage, &category, denom_or_address, period)?;
+            Ok(BudgetLine {
+                denom_or_address: denom_or_address.clone(),
+                allocated: allocation.amount,
+                spent,
+                pending,
+                remaining: allocation
+                    .amount
+                    .saturating_sub(spent.checked_add(pending)?),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(BudgetResponse {
+        category,
+        period_in_days: budget.period_in_days,
+        period_start: budget.period_start(period)?,
+        period_end: budget.period_start(period + 1)?,
+        lines,
+    })
+}
+
+pub fn list_schedules(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SchedulesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let schedules = SCHEDULES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, schedule)| schedule))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(SchedulesResponse { schedules })
+}
+
+pub fn get_stream(deps: Deps, env: Env, id: u64) -> StdResult<StreamResponse> {
+    let stream = STREAMS.load(deps.storage, id)?;
+    let streamed = stream.streamed(env.block.time);
+
+    // a cancelled stream won't accrue any more
+    let remaining = match stream.cancelled_at {
+        Some(_) => Uint128::zero(),
+        None => stream.total - streamed,
+    };
+
+    Ok(StreamResponse {
+        withdrawn: stream.withdrawn,
+        withdrawable: streamed - stream.withdrawn,
+        streamed,
+        remaining,
+        stream,
+    })
+}
+
+pub fn get_agreement(deps: Deps, id: u64) -> StdResult<Agreement> {
+    AGREEMENTS.load(deps.storage, id)
+}
+
+pub fn list_agreements(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AgreementsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let agreements = AGREEMENTS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, agreement)| agreement))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AgreementsResponse { agreements })
+}
@@ -1,30 +1,641 @@
+use std::fmt;
+
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Timestamp, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, CosmosMsg, Empty, StdResult, Timestamp, Uint128, Uint64};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub enable_cw20_receive: bool,
-    pub set_withdraw_as_immutable: bool,
-    pub set_override_as_immutable: bool,
+    // the fields that can no longer be changed
+    // a lock can never be removed
+    #[serde(default)]
+    pub locked_fields: Vec<LockableField>,
     pub withdraw_address: Addr,
-    pub override_address: Addr,
+    // None once the override role has been renounced
+    pub override_address: Option<Addr>,
     pub withdraw_delay_in_days: u64,
-    pub native_denom: String,
+    pub recovery_address: Option<Addr>,
+    pub inactivity_period_in_days: Option<u64>,
+    pub override_heartbeat_period_in_days: Option<u64>,
+    pub fallback_override_address: Option<Addr>,
+    // the chain's unbonding period, which can't be queried
+    // used to warn if an undelegation outlasts the withdraw delay
+    #[serde(default)]
+    pub unbonding_period_in_days: Option<u64>,
+    // if set, proposed executions are limited to these
+    // otherwise anything goes
+    #[serde(default)]
+    pub execution_allow_list: Option<ExecutionAllowList>,
+    // lets anyone trigger a ready withdrawal
+    // the funds still only go to the recipient
+    #[serde(default)]
+    pub permissionless_execution: bool,
+    // paid from the keeper pool to whoever triggers a ready withdrawal
+    // if it isn't the recipient
+    #[serde(default)]
+    pub keeper_tip: Option<Coin>,
+    // a cold key that can cancel a withdrawal by signing off-chain
+    #[serde(default)]
+    pub guardian_key: Option<GuardianKey>,
+    // where the override_address can evacuate funds to
+    // this can never be changed
+    #[serde(default)]
+    pub safe_harbor_address: Option<Addr>,
+    // the least of each asset that has to stay in escrow
+    // unless a withdrawal opts into the reserve delay
+    #[serde(default)]
+    pub reserve_floors: Vec<ReserveFloor>,
+    // the longer delay for withdrawals that dip into the reserve
+    // if unset, the reserve can't be withdrawn at all
+    #[serde(default)]
+    pub reserve_delay_in_days: Option<u64>,
+    // how long a depositor has to take back a mistaken deposit
+    // if unset, only the override_address can refund one
+    #[serde(default)]
+    pub deposit_refund_period_in_days: Option<u64>,
+}
+
+impl Config {
+    pub fn is_locked(&self, field: &LockableField) -> bool {
+        self.locked_fields.contains(field)
+    }
+
+    // the reserve floor for an asset, zero if there isn't one
+    pub fn reserve_floor(&self, denom_or_address: &str) -> Uint128 {
+        self.reserve_floors
+            .iter()
+            .find(|floor| floor.denom_or_address == denom_or_address)
+            .map(|floor| floor.amount)
+            .unwrap_or_default()
+    }
+
+    // replaces the reserve floor for an asset
+    // a zero amount removes it
+    pub fn set_reserve_floor(&mut self, denom_or_address: String, amount: Uint128) {
+        self.reserve_floors
+            .retain(|floor| floor.denom_or_address != denom_or_address);
+        if !amount.is_zero() {
+            self.reserve_floors.push(ReserveFloor {
+                denom_or_address,
+                amount,
+            });
+        }
+    }
+
+    // when the recovery_address is allowed to step in,
+    // given the last time the withdraw_address checked in
+    pub fn recovery_available_at(&self, last_heartbeat: Timestamp) -> Option<Timestamp> {
+        let seconds_in_day = 86400u64;
+        match (&self.recovery_address, self.inactivity_period_in_days) {
+            (Some(_), Some(days)) => Some(last_heartbeat.plus_seconds(days * seconds_in_day)),
+            _ => None,
+        }
+    }
+
+    // when the override_address is considered to have lapsed,
+    // given the last time it checked in
+    pub fn override_lapses_at(&self, last_heartbeat: Timestamp) -> Option<Timestamp> {
+        let seconds_in_day = 86400u64;
+        self.override_heartbeat_period_in_days
+            .map(|days| last_heartbeat.plus_seconds(days * seconds_in_day))
+    }
+
+    // the address that can currently cancel withdrawals and update the addresses
+    // this is the fallback_override_address if the override_address
+    // has missed its check-in window, otherwise the override_address
+    pub fn active_override_address(
+        &self,
+        last_heartbeat: Timestamp,
+        now: Timestamp,
+    ) -> Option<Addr> {
+        let lapsed = matches!(self.override_lapses_at(last_heartbeat), Some(at) if now > at);
+        match (&self.fallback_override_address, lapsed) {
+            (Some(fallback), true) => Some(fallback.clone()),
+            _ => self.override_address.clone(),
+        }
+    }
+}
+
+// the least of an asset that has to stay in escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReserveFloor {
+    pub denom_or_address: String,
+    pub amount: Uint128,
+}
+
+// the parts of the config that can be locked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LockableField {
+    WithdrawAddress,
+    OverrideAddress,
+    WithdrawDelay,
+    Cw20Receive,
+    NativeDenoms,
+}
+
+impl fmt::Display for LockableField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            LockableField::WithdrawAddress => "withdraw_address",
+            LockableField::OverrideAddress => "override_address",
+            LockableField::WithdrawDelay => "withdraw_delay",
+            LockableField::Cw20Receive => "cw20_receive",
+            LockableField::NativeDenoms => "native_denoms",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// older versions stored immutability as a pair of bools on the config
+// this reads just those, so they can be turned into locks
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct LegacyImmutability {
+    #[serde(default)]
+    pub set_withdraw_as_immutable: bool,
+    #[serde(default)]
+    pub set_override_as_immutable: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Withdrawal {
+    #[serde(default)]
+    pub id: u64,
+    pub ready_at: Timestamp,
+    pub denom_or_address: String,
+    pub amount: Uint128,
+    #[serde(default)]
+    pub is_recovery: bool,
+    // set if this is a withdrawal of NFTs
+    // in which case denom_or_address is the collection
+    #[serde(default)]
+    pub nft_token_ids: Option<Vec<String>>,
+    // the vault this is withdrawn from
+    // None is the default vault
+    #[serde(default)]
+    pub vault: Option<String>,
+    // the budget category this is spent against, if any
+    // along with the period it was started in
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub budget_period: u64,
+    // set if this waited out the reserve delay
+    // in which case it can dip into the reserve
+    #[serde(default)]
+    pub use_reserve_delay: bool,
+}
+
+// a named vault, with its own delay
+// anything not credited to a named vault is in the default vault,
+// which uses the withdraw delay
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Vault {
+    pub name: String,
+    pub delay_in_days: u64,
+    // the most of an asset that can be taken out in one withdrawal
+    pub limits: Vec<VaultLimit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VaultLimit {
+    pub denom_or_address: String,
+    pub amount: Uint128,
+}
+
+impl Vault {
+    pub fn limit(&self, denom_or_address: &str) -> Option<Uint128> {
+        self.limits
+            .iter()
+            .find(|limit| limit.denom_or_address == denom_or_address)
+            .map(|limit| limit.amount)
+    }
+}
+
+// a move between vaults, waiting out the delay of the vault it leaves
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct VaultTransfer {
+    pub id: u64,
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub denom_or_address: String,
+    pub amount: Uint128,
     pub ready_at: Timestamp,
+}
+
+// a category of spending, e.g. ops or grants
+// with how much of each asset it can spend per period
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BudgetCategory {
+    pub name: String,
+    pub period_in_days: u64,
+    pub allocations: Vec<BudgetAllocation>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BudgetAllocation {
+    pub denom_or_address: String,
+    pub amount: Uint128,
+}
+
+impl BudgetCategory {
+    // the allocation for an asset, zero if there isn't one
+    pub fn allocation(&self, denom_or_address: &str) -> Uint128 {
+        self.allocations
+            .iter()
+            .find(|allocation| allocation.denom_or_address == denom_or_address)
+            .map(|allocation| allocation.amount)
+            .unwrap_or_default()
+    }
+
+    fn period_in_seconds(&self) -> StdResult<Uint64> {
+        Ok(Uint64::from(self.period_in_days).checked_mul(Uint64::from(86400u64))?)
+    }
+
+    // periods are counted from the unix epoch
+    pub fn period(&self, time: Timestamp) -> StdResult<u64> {
+        Ok(time.seconds() / self.period_in_seconds()?.u64())
+    }
+
+    pub fn period_start(&self, period: u64) -> StdResult<Timestamp> {
+        let seconds = Uint64::from(period).checked_mul(self.period_in_seconds()?)?;
+        Ok(Timestamp::from_seconds(seconds.u64()))
+    }
+}
+
+// what a category has spent of an asset in a period
+// anything from an earlier period no longer counts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BudgetSpend {
+    pub period: u64,
+    pub amount: Uint128,
+}
+
+// a recurring payout, e.g. a contributor's monthly pay
+// the first instalment is due once the withdraw delay has passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Schedule {
+    pub id: u64,
+    pub recipient: Addr,
+    pub denom_or_address: String,
+    // paid per instalment
+    pub amount: Uint128,
+    pub interval_in_days: u64,
+    // how many instalments there are in total
+    pub count: u64,
+    pub paid: u64,
+    pub next_payment_at: Timestamp,
+    pub paused: bool,
+}
+
+// a payment that accrues every second between start and end
+// the funds stay in escrow until the recipient withdraws them
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Stream {
+    pub id: u64,
+    pub recipient: Addr,
+    pub denom_or_address: String,
+    pub total: Uint128,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub withdrawn: Uint128,
+    // accrual stops here, if the stream was cancelled
+    pub cancelled_at: Option<Timestamp>,
+}
+
+impl Stream {
+    // how much has accrued to the recipient by a given time
+    pub fn streamed(&self, time: Timestamp) -> Uint128 {
+        let until = match self.cancelled_at {
+            Some(cancelled_at) => time.min(cancelled_at),
+            None => time,
+        };
+        if until <= self.start {
+            return Uint128::zero();
+        }
+        if until >= self.end {
+            return self.total;
+        }
+        self.total.multiply_ratio(
+            until.seconds() - self.start.seconds(),
+            self.end.seconds() - self.start.seconds(),
+        )
+    }
+}
+
+// a grant paid against milestones
+// the payer is always the withdraw_address
+// and the override_address is the arbiter of last resort
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Agreement {
+    pub id: u64,
+    pub payee: Addr,
+    pub arbiter: Option<Addr>,
     pub denom_or_address: String,
+    pub milestones: Vec<Milestone>,
+    // nothing can be paid out before this
+    // which gives the override_address time to cancel it
+    pub active_at: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Milestone {
+    pub amount: Uint128,
+    // if it hasn't been approved or disputed by then, it can be refunded
+    pub deadline: Timestamp,
+    pub status: MilestoneStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneStatus {
+    Pending,
+    Disputed,
+    Paid,
+    Refunded,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureAlgorithm {
+    Secp256k1,
+    Ed25519,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct GuardianKey {
+    pub algorithm: SignatureAlgorithm,
+    pub public_key: Binary,
+}
+
+// the kinds of message a proposed execution can be made up of
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMsgType {
+    Bank,
+    Wasm,
+    Staking,
+    Distribution,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ExecutionAllowList {
+    // the kinds of message that are allowed
+    pub msg_types: Vec<ExecutionMsgType>,
+    // the contracts that wasm messages can be sent to
+    pub contracts: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Executed,
+    Cancelled,
+}
+
+// a set of messages the contract will execute
+// once the withdraw delay has passed
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Proposal {
+    pub id: u64,
+    pub msgs: Vec<CosmosMsg>,
+    // the delay this was proposed with
+    pub delay_in_days: u64,
+    pub ready_at: Timestamp,
+    pub status: ProposalStatus,
+}
+
+// something held in escrow
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Asset {
+    Native {
+        denom: String,
+        amount: Uint128,
+    },
+    Cw20 {
+        address: String,
+        amount: Uint128,
+    },
+    Nft {
+        collection: String,
+        token_ids: Vec<String>,
+    },
+}
+
+// a record of funds moved to the safe_harbor_address
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Evacuation {
+    pub id: u64,
+    pub evacuated_by: Addr,
+    pub evacuated_at: Timestamp,
+    pub safe_harbor_address: Addr,
+    pub assets: Vec<Asset>,
+}
+
+// a change to the config that has to sit out the withdraw delay
+// before it can be applied
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigUpdate {
+    /// Accept deposits of this CW20 via the receive hook
+    AddAcceptedCw20 { address: String },
+    /// Stop accepting deposits of this CW20
+    RemoveAcceptedCw20 { address: String },
+    /// Turn the CW20 receive hook on or off
+    SetCw20Receive { enabled: bool },
+    /// Allow withdrawals of this native denom
+    AddNativeDenom { denom: String },
+    /// Stop allowing withdrawals of this native denom
+    RemoveNativeDenom { denom: String },
+    /// Set the chain's unbonding period
+    SetUnbondingPeriod { days: Option<u64> },
+    /// Limit proposed executions to an allow-list
+    /// or lift the limit, if None
+    SetExecutionAllowList {
+        allow_list: Option<ExecutionAllowList>,
+    },
+    /// Let anyone trigger a ready withdrawal, or not
+    SetPermissionlessExecution { enabled: bool },
+    /// Set the tip paid to keepers from the keeper pool
+    /// or stop paying one, if None
+    SetKeeperTip { tip: Option<Coin> },
+    /// Set the guardian key that can sign cancellations
+    /// or remove it, if None
+    SetGuardianKey { key: Option<GuardianKey> },
+    /// Set the delay on withdrawals, config updates and proposals
+    /// anything already pending keeps its original delay
+    /// this can be at most 3650 days
+    SetWithdrawDelay { days: u64 },
+    /// Set the reserve floor for a native denom or CW20
+    /// or remove it, if the amount is zero
+    SetReserveFloor {
+        denom_or_address: String,
+        amount: Uint128,
+    },
+    /// Set the delay on withdrawals that dip into the reserve
+    /// or stop the reserve being withdrawn at all, if None
+    /// this has to be longer than the withdraw delay, and at most 3650 days
+    SetReserveDelay { days: Option<u64> },
+    /// Set how long a depositor has to take back a mistaken deposit
+    /// or stop depositors refunding themselves, if None
+    SetDepositRefundPeriod { days: Option<u64> },
+    /// Add a named vault, or change one
+    /// this waits out the longer of the withdraw delay
+    /// and the vault's current delay
+    /// a vault's delay can be at most 3650 days
+    SetVault {
+        name: String,
+        delay_in_days: u64,
+        limits: Vec<VaultLimit>,
+    },
+    /// Remove a named vault, which has to be empty
+    /// this waits out the longer of the withdraw delay
+    /// and the vault's delay
+    RemoveVault { name: String },
+    /// Add a budget category, or change its allocations
+    /// spending so far this period still counts,
+    /// unless the period length changes, which starts the count over
+    SetBudgetCategory {
+        name: String,
+        period_in_days: u64,
+        allocations: Vec<BudgetAllocation>,
+    },
+    /// Remove a budget category
+    RemoveBudgetCategory { name: String },
+}
+
+impl ConfigUpdate {
+    // the lockable field this update would change, if any
+    pub fn locked_by(&self) -> Option<LockableField> {
+        match self {
+            ConfigUpdate::SetCw20Receive { .. } => Some(LockableField::Cw20Receive),
+            ConfigUpdate::AddNativeDenom { .. } | ConfigUpdate::RemoveNativeDenom { .. } => {
+                Some(LockableField::NativeDenoms)
+            }
+            ConfigUpdate::SetWithdrawDelay { .. } => Some(LockableField::WithdrawDelay),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingConfigUpdate {
+    pub ready_at: Timestamp,
+    pub update: ConfigUpdate,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Deposit {
+    pub id: u64,
+    pub cw20_address: Addr,
+    pub depositor: Addr,
     pub amount: Uint128,
+    pub memo: Option<String>,
+    pub vault: Option<String>,
+    pub received_at: Timestamp,
+    #[serde(default)]
+    pub refunded: bool,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
+// the same storage as CONFIG, read as it was by older versions
+pub const LEGACY_IMMUTABILITY: Item<LegacyImmutability> = Item::new("config");
+
+pub const PENDING_CONFIG_UPDATE: Item<Option<PendingConfigUpdate>> =
+    Item::new("pending_config_update");
+
+// when a pending renounce of the override role can be confirmed
+pub const PENDING_RENOUNCE: Item<Option<Timestamp>> = Item::new("pending_renounce");
+
 pub const WITHDRAWAL_READY: Item<Option<Withdrawal>> = Item::new("withdrawal_ready");
 
+// how many withdrawals have been started, used for withdrawal ids
+pub const WITHDRAWAL_COUNT: Item<u64> = Item::new("withdrawal_count");
+
+// the last nonce the guardian_key signed with
+// every signed cancellation has to use a higher one
+pub const LAST_GUARDIAN_NONCE: Item<u64> = Item::new("last_guardian_nonce");
+
+// the last time the withdraw_address proved it was still alive
+pub const LAST_WITHDRAW_HEARTBEAT: Item<Timestamp> = Item::new("last_withdraw_heartbeat");
+
+// the last time the override_address proved it was still alive
+pub const LAST_OVERRIDE_HEARTBEAT: Item<Timestamp> = Item::new("last_override_heartbeat");
+
 // a mapping of CW20 contract_address -> balance held by this contract
 pub const CW20_BALANCES: Map<Addr, Uint128> = Map::new("cw20_balances");
+
+// the native denoms that can be withdrawn
+pub const NATIVE_DENOMS: Map<String, Empty> = Map::new("native_denoms");
+
+// the CW20 contracts that are allowed to call the receive hook
+pub const ACCEPTED_CW20S: Map<Addr, Empty> = Map::new("accepted_cw20s");
+
+// a mapping of (CW20 contract_address, depositor) -> total amount deposited
+pub const CW20_DEPOSITS: Map<(Addr, Addr), Uint128> = Map::new("cw20_deposits");
+
+// a mapping of (CW721 contract_address, token_id) -> the address that sent it
+pub const NFTS: Map<(Addr, String), Addr> = Map::new("nfts");
+
+// every CW20 deposit, by id
+pub const DEPOSITS: Map<u64, Deposit> = Map::new("deposits");
+
+pub const DEPOSIT_COUNT: Item<u64> = Item::new("deposit_count");
+
+// a mapping of (denom_or_address, vault name) -> balance credited to that vault
+// keyed by asset first, so the vaults holding an asset can be found without a full scan
+pub const VAULT_BALANCES: Map<(String, String), Uint128> = Map::new("vault_balances");
+
+// the named vaults, by name
+// a vault name with no entry here uses the withdraw delay
+pub const VAULTS: Map<String, Vault> = Map::new("vaults");
+
+// moves between vaults that are waiting out a delay
+pub const VAULT_TRANSFERS: Map<u64, VaultTransfer> = Map::new("vault_transfers");
+pub const VAULT_TRANSFER_COUNT: Item<u64> = Item::new("vault_transfer_count");
+
+// budget categories, keyed by name
+pub const BUDGET_CATEGORIES: Map<String, BudgetCategory> = Map::new("budget_categories");
+
+// spending per (category, denom or address)
+pub const BUDGET_SPEND: Map<(String, String), BudgetSpend> = Map::new("budget_spend");
+
+// recurring payouts, keyed by id
+// a schedule is removed once it's paid out, or cancelled
+pub const SCHEDULES: Map<u64, Schedule> = Map::new("schedules");
+pub const SCHEDULE_COUNT: Item<u64> = Item::new("schedule_count");
+
+// payment streams, keyed by id
+pub const STREAMS: Map<u64, Stream> = Map::new("streams");
+pub const STREAM_COUNT: Item<u64> = Item::new("stream_count");
+
+// milestone agreements, keyed by id
+pub const AGREEMENTS: Map<u64, Agreement> = Map::new("agreements");
+pub const AGREEMENT_COUNT: Item<u64> = Item::new("agreement_count");
+
+// how much of each asset is set aside for unsettled milestones
+// this can't be withdrawn from the default vault
+pub const COMMITTED_TO_AGREEMENTS: Map<String, Uint128> = Map::new("committed_to_agreements");
+
+// how much of each asset is set aside for streams, less what's been withdrawn
+// this can't be withdrawn from the default vault either
+pub const COMMITTED_TO_STREAMS: Map<String, Uint128> = Map::new("committed_to_streams");
+
+// every proposed execution, by id
+pub const PROPOSALS: Map<u64, Proposal> = Map::new("proposals");
+
+pub const PROPOSAL_COUNT: Item<u64> = Item::new("proposal_count");
+
+// every evacuation ever made, by id
+pub const EVACUATIONS: Map<u64, Evacuation> = Map::new("evacuations");
+pub const EVACUATION_COUNT: Item<u64> = Item::new("evacuation_count");
+
+// a mapping of native denom -> amount set aside to tip keepers
+// this is kept apart from the escrowed balance
+pub const KEEPER_POOL: Map<String, Uint128> = Map::new("keeper_pool");